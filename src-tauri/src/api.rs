@@ -2,6 +2,7 @@ use tauri_plugin_dialog::{DialogExt, FileDialogBuilder};
 use tokio::sync::oneshot;
 
 use serde::Deserialize;
+use tauri::ipc::Channel;
 use tauri::State;
 
 use std::collections::HashMap;
@@ -16,7 +17,16 @@ use cocoa::foundation::NSString;
 #[cfg(target_os = "macos")]
 use objc::{class, msg_send, sel, sel_impl};
 
-use crate::db::{self, builder, schema, Driver, Registry, QueryResult};
+use crate::db::cancel::QueryTag;
+use crate::db::changeset::{self, ChangeSet, ChangeSetResult};
+use crate::db::count::{self, CountMode};
+use crate::db::keyset;
+use crate::db::schema::{ObjectFilter, ObjectList, ObjectRef};
+use crate::db::script::{ScriptOptions, ScriptResult};
+use crate::db::session::{self, TransactionState};
+use crate::db::builder::Spec;
+use crate::db::validate::{self, SpecError};
+use crate::db::{self, builder, edit, BatchSink, Driver, QueryEvent, Registry, QueryResult};
 
 static SCOPE_MAP: OnceLock<Mutex<HashMap<String, String>>> = OnceLock::new();
 static SCOPE_NEXT_ID: AtomicUsize = AtomicUsize::new(1);
@@ -55,6 +65,10 @@ pub struct ExecArgs {
     pub conn_id: String,
     pub sql: String,
     pub limit: Option<u32>,
+    /// Client-chosen handle; pass it to `cancel_query` to abort the query.
+    pub query_id: Option<String>,
+    /// Run inside this transaction (see `begin_transaction`).
+    pub session_id: Option<String>,
 }
 
 #[tauri::command]
@@ -64,66 +78,297 @@ pub async fn execute_sql(reg: State<'_, Registry>, args: ExecArgs) -> Result<Que
         &args.conn_id,
         &args.sql,
         args.limit.unwrap_or(1000),
+        args.query_id.as_deref(),
+        args.session_id.as_deref(),
     )
     .await
     .map_err(|e| e.to_string())
 }
 
+#[derive(Deserialize)]
+pub struct ScriptArgs {
+    pub conn_id: String,
+    pub sql: String,
+    /// Row cap per result set.
+    pub limit: Option<u32>,
+    #[serde(flatten)]
+    pub options: ScriptOptions,
+    pub query_id: Option<String>,
+}
+
+/// Run a multi-statement script and return one outcome per executed statement.
+#[tauri::command]
+pub async fn execute_script(reg: State<'_, Registry>, args: ScriptArgs) -> Result<ScriptResult, String> {
+    db::execute_script(
+        &reg,
+        &args.conn_id,
+        &args.sql,
+        args.options,
+        args.limit.unwrap_or(1000),
+        args.query_id.as_deref(),
+    )
+    .await
+    .map_err(|e| e.to_string())
+}
+
+#[derive(Deserialize)]
+pub struct StreamExecArgs {
+    pub conn_id: String,
+    pub sql: String,
+    pub limit: Option<u32>,
+    pub batch_size: Option<usize>,
+    pub query_id: Option<String>,
+    pub session_id: Option<String>,
+}
+
+/// Run a query and push rows to `on_event` in batches while they arrive.
+/// The first event carries the query id used by `cancel_query`.
+/// The returned `QueryResult` carries the columns and `truncated`; its rows are empty.
+#[tauri::command]
+pub async fn execute_sql_stream(
+    reg: State<'_, Registry>,
+    args: StreamExecArgs,
+    on_event: Channel<QueryEvent>,
+) -> Result<QueryResult, String> {
+    let query_id = args.query_id.unwrap_or_else(db::new_query_id);
+    let mut sink = batch_sink(on_event, args.batch_size, &query_id, None)?;
+    db::stream_sql(
+        &reg,
+        &args.conn_id,
+        &args.sql,
+        args.limit.unwrap_or(1000),
+        Some(&query_id),
+        args.session_id.as_deref(),
+        &mut sink,
+    )
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// `spec` is set for builder queries so streamed column metadata points back
+/// at the source tables, as in the final `QueryResult`.
+fn batch_sink(
+    channel: Channel<QueryEvent>,
+    batch_size: Option<usize>,
+    query_id: &str,
+    spec: Option<builder::SelectSpec>,
+) -> Result<BatchSink<'static>, String> {
+    channel
+        .send(QueryEvent::Started { query_id: query_id.to_string() })
+        .map_err(|e| e.to_string())?;
+    Ok(BatchSink {
+        batch_size: batch_size.unwrap_or(200).max(1),
+        emit: Box::new(move |mut ev| {
+            if let (Some(spec), QueryEvent::Columns { column_meta, .. }) = (&spec, &mut ev) {
+                builder::mark_sources(column_meta, spec);
+            }
+            channel.send(ev).map_err(|e| anyhow::anyhow!(e.to_string()))
+        }),
+    })
+}
+
+#[derive(Deserialize)]
+pub struct BeginTransactionArgs {
+    pub conn_id: String,
+}
+
+/// Start a transaction on a dedicated connection. Pass the returned
+/// `session_id` to the execute commands to run statements inside it.
+#[tauri::command]
+pub async fn begin_transaction(
+    reg: State<'_, Registry>,
+    args: BeginTransactionArgs,
+) -> Result<TransactionState, String> {
+    session::begin(&reg, &args.conn_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[derive(Deserialize)]
+pub struct SessionArgs {
+    pub session_id: String,
+}
+
+#[tauri::command]
+pub async fn commit(reg: State<'_, Registry>, args: SessionArgs) -> Result<(), String> {
+    session::commit(&reg, &args.session_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn rollback(reg: State<'_, Registry>, args: SessionArgs) -> Result<(), String> {
+    session::rollback(&reg, &args.session_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Whether the transaction has uncommitted changes.
+#[tauri::command]
+pub async fn transaction_state(
+    reg: State<'_, Registry>,
+    args: SessionArgs,
+) -> Result<TransactionState, String> {
+    session::state(&reg, &args.session_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[derive(Deserialize)]
+pub struct CancelArgs {
+    pub query_id: String,
+}
+
+/// Abort a running query; resolves to false if it had already finished.
+#[tauri::command]
+pub async fn cancel_query(reg: State<'_, Registry>, args: CancelArgs) -> Result<bool, String> {
+    db::cancel_query(&reg, &args.query_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
 #[derive(Deserialize)]
 pub struct SchemaArgs {
     pub conn_id: String,
+    /// Load only matching schemas and tables; skips the schema cache.
+    #[serde(default)]
+    pub filter: Option<ObjectFilter>,
+    /// Introspect again instead of answering from the cache.
+    #[serde(default)]
+    pub refresh: bool,
 }
 
 #[tauri::command]
 pub async fn get_schema(reg: State<'_, Registry>, args: SchemaArgs) -> Result<serde_json::Value, String> {
-    use serde_json::{json, Value};
-
-    let pools = reg.inner.read().await;
-    let pool = pools
-        .get(&args.conn_id)
-        .ok_or_else(|| "connection not found".to_string())?;
-
-    let s = schema::inspect_schema(pool).await.map_err(|e| e.to_string())?;
-    let mut v = serde_json::to_value(s).unwrap_or_else(|_| json!({}));
-
-    // Ensure new fields are present in the JSON payload for each column.
-    if let Some(obj) = v.as_object_mut() {
-        if let Some(tables) = obj.get_mut("tables").and_then(|t| t.as_array_mut()) {
-            for t in tables.iter_mut() {
-                if let Some(cols) = t.get_mut("columns").and_then(|c| c.as_array_mut()) {
-                    for c in cols.iter_mut() {
-                        if let Some(col) = c.as_object_mut() {
-                            col.entry("primaryKey").or_insert(Value::Bool(false));
-                            // If backend provided `not_null` accidentally, convert it to `nullable`
-                            if let Some(nn) = col.remove("not_null") { // legacy field -> boolean
-                                let nullable = match nn {
-                                    Value::Bool(b) => Value::Bool(!b),
-                                    Value::Number(n) => Value::Bool(n.as_i64().unwrap_or(0) == 0),
-                                    _ => Value::Bool(true),
-                                };
-                                col.insert("nullable".into(), nullable);
-                            }
-                            col.entry("nullable").or_insert(Value::Bool(true));
-                            col.entry("length").or_insert(Value::Null);
-                            col.entry("precision").or_insert(Value::Null);
-                            col.entry("scale").or_insert(Value::Null);
-                            // normalise default -> defaultValue if needed
-                            if let Some(def) = col.remove("default") {
-                                col.insert("defaultValue".into(), def);
-                            } else {
-                                col.entry("defaultValue").or_insert(Value::Null);
-                            }
-                            // normalise data_type -> type if needed
-                            if let Some(dt) = col.remove("data_type") {
-                                col.insert("type".into(), dt);
-                            }
-                        }
-                    }
+    use serde_json::json;
+
+    let mut v = match &args.filter {
+        Some(filter) => {
+            let s = db::filtered_schema(&reg, &args.conn_id, filter).await.map_err(|e| e.to_string())?;
+            serde_json::to_value(&s)
+        }
+        None => {
+            let s = if args.refresh {
+                db::refresh_schema(&reg, &args.conn_id).await
+            } else {
+                db::cached_schema(&reg, &args.conn_id).await
+            }
+            .map_err(|e| e.to_string())?;
+            serde_json::to_value(&*s)
+        }
+    }
+    .unwrap_or_else(|_| json!({}));
+
+    if let Some(tables) = v.get_mut("tables").and_then(|t| t.as_array_mut()) {
+        tables.iter_mut().for_each(normalise_table);
+    }
+    Ok(v)
+}
+
+// Ensure new fields are present in the JSON payload for each column.
+fn normalise_table(t: &mut serde_json::Value) {
+    use serde_json::Value;
+
+    if let Some(cols) = t.get_mut("columns").and_then(|c| c.as_array_mut()) {
+        for c in cols.iter_mut() {
+            if let Some(col) = c.as_object_mut() {
+                col.entry("primaryKey").or_insert(Value::Bool(false));
+                // If backend provided `not_null` accidentally, convert it to `nullable`
+                if let Some(nn) = col.remove("not_null") { // legacy field -> boolean
+                    let nullable = match nn {
+                        Value::Bool(b) => Value::Bool(!b),
+                        Value::Number(n) => Value::Bool(n.as_i64().unwrap_or(0) == 0),
+                        _ => Value::Bool(true),
+                    };
+                    col.insert("nullable".into(), nullable);
+                }
+                col.entry("nullable").or_insert(Value::Bool(true));
+                col.entry("length").or_insert(Value::Null);
+                col.entry("precision").or_insert(Value::Null);
+                col.entry("scale").or_insert(Value::Null);
+                // normalise default -> defaultValue if needed
+                if let Some(def) = col.remove("default") {
+                    col.insert("defaultValue".into(), def);
+                } else {
+                    col.entry("defaultValue").or_insert(Value::Null);
+                }
+                // normalise data_type -> type if needed
+                if let Some(dt) = col.remove("data_type") {
+                    col.insert("type".into(), dt);
                 }
             }
         }
     }
+}
+
+#[derive(Deserialize)]
+pub struct SchemaChangedArgs {
+    pub conn_id: String,
+}
+
+/// Whether the schema changed since `get_schema` last introspected it, e.g.
+/// by a migration from another client; cheap enough to poll.
+#[tauri::command]
+pub async fn schema_changed(reg: State<'_, Registry>, args: SchemaChangedArgs) -> Result<bool, String> {
+    db::schema_changed(&reg, &args.conn_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[derive(Deserialize)]
+pub struct ListObjectsArgs {
+    pub conn_id: String,
+    #[serde(flatten)]
+    pub filter: ObjectFilter,
+}
+
+/// Schemas, tables and views for a fast first paint; see `describe_table`.
+#[tauri::command]
+pub async fn list_objects(reg: State<'_, Registry>, args: ListObjectsArgs) -> Result<ObjectList, String> {
+    db::list_objects(&reg, &args.conn_id, &args.filter)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[derive(Deserialize)]
+pub struct DescribeTableArgs {
+    pub conn_id: String,
+    pub schema: Option<String>,
+    pub table: String,
+}
+
+/// Columns, keys, indexes and constraints of one table, in `get_schema` form.
+#[tauri::command]
+pub async fn describe_table(reg: State<'_, Registry>, args: DescribeTableArgs) -> Result<serde_json::Value, String> {
+    let refs = [ObjectRef { schema: args.schema, name: args.table }];
+    let t = db::describe_tables(&reg, &args.conn_id, &refs)
+        .await
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .next()
+        .ok_or_else(|| format!("table {} not found", refs[0].name))?;
+    let mut v = serde_json::to_value(t).map_err(|e| e.to_string())?;
+    normalise_table(&mut v);
+    Ok(v)
+}
+
+#[derive(Deserialize)]
+pub struct DescribeTablesArgs {
+    pub conn_id: String,
+    pub tables: Vec<ObjectRef>,
+}
 
+/// `describe_table` for several tables at once, e.g. those visible in the ER
+/// view; unknown tables are left out.
+#[tauri::command]
+pub async fn describe_tables(reg: State<'_, Registry>, args: DescribeTablesArgs) -> Result<serde_json::Value, String> {
+    let tables = db::describe_tables(&reg, &args.conn_id, &args.tables)
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut v = serde_json::to_value(tables).map_err(|e| e.to_string())?;
+    if let Some(tables) = v.as_array_mut() {
+        tables.iter_mut().for_each(normalise_table);
+    }
     Ok(v)
 }
 
@@ -131,22 +376,241 @@ pub async fn get_schema(reg: State<'_, Registry>, args: SchemaArgs) -> Result<se
 pub struct SelectSpecArgs {
     pub conn_id: String,
     pub spec: builder::SelectSpec,
+    pub query_id: Option<String>,
+    pub session_id: Option<String>,
+    /// Also return the total row count in `QueryResult.total`.
+    pub count: Option<CountMode>,
 }
 
 #[tauri::command]
 pub async fn execute_select_spec(
     reg: State<'_, Registry>,
-    args: SelectSpecArgs,
+    mut args: SelectSpecArgs,
 ) -> Result<QueryResult, String> {
-    let pools = reg.inner.read().await;
-    let pool = pools.get(&args.conn_id).ok_or_else(|| "connection not found".to_string())?;
+    let errors = db::check_spec(&reg, &args.conn_id, &mut args.spec)
+        .await
+        .map_err(|e| e.to_string())?;
+    validate::ensure(errors).map_err(|e| e.to_string())?;
+    let page = match args.spec.keyset {
+        Some(_) => {
+            let schema = db::cached_schema(&reg, &args.conn_id).await.map_err(|e| e.to_string())?;
+            Some(keyset::plan(&schema, &args.spec).map_err(|e| e.to_string())?)
+        }
+        None => None,
+    };
+    let spec = page.as_ref().map_or(&args.spec, |p| &p.spec);
+
+    let pool = &db::pool_of(&reg, &args.conn_id).await.map_err(|e| e.to_string())?;
     let dialect = pool.dialect();
-    let (sql, values) = builder::build_select(&args.spec, dialect);
-    db::execute_sql_with_binds(pool, &sql, values, 1000)
+    let (sql, values) = match &page {
+        Some(p) => p.build(dialect),
+        None => builder::build_select(spec, dialect),
+    }
+    .map_err(|e| e.to_string())?;
+    let tag = args.query_id.as_deref().map(|query_id| QueryTag {
+        running: &reg.running,
+        conn_id: &args.conn_id,
+        query_id,
+    });
+    let query_id = args.query_id.as_deref();
+    let session_id = args.session_id.as_deref();
+    let rows = async {
+        match session_id {
+            Some(session_id) => {
+                session::run(&reg, &args.conn_id, session_id, &sql, Some(values), 1000, None, query_id).await
+            }
+            None => db::execute_sql_with_binds(pool, &sql, values, 1000, tag.as_ref()).await,
+        }
+    };
+    // over the same filters; cancelling `query_id` stops it too
+    let total = async {
+        match args.count {
+            Some(mode) => count::total(&reg, pool, &args.conn_id, session_id, &args.spec, mode, query_id).await,
+            None => Ok(None),
+        }
+    };
+    // a session runs one statement at a time, so the count follows the page
+    // there and runs alongside it otherwise
+    let (res, total) = match session_id {
+        Some(_) => {
+            let res = rows.await.map_err(|e| e.to_string())?;
+            (res, total.await.map_err(|e| e.to_string())?)
+        }
+        None => tokio::try_join!(rows, total).map_err(|e| e.to_string())?,
+    };
+    let res = res.with_sources(spec);
+    let mut res = match &page {
+        Some(p) => p.finish(res).map_err(|e| e.to_string())?,
+        None => res,
+    };
+    res.total = total;
+    Ok(res)
+}
+
+#[derive(Deserialize)]
+pub struct SpecArgs {
+    pub conn_id: String,
+    pub spec: Spec,
+}
+
+/// Check a spec against the connection's schema; empty when it is valid.
+#[tauri::command]
+pub async fn validate_spec(reg: State<'_, Registry>, mut args: SpecArgs) -> Result<Vec<SpecError>, String> {
+    db::check_spec(&reg, &args.conn_id, &mut args.spec)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// SQL a spec would run, inlined for display and in parameterized form.
+#[tauri::command]
+pub async fn preview_spec_sql(reg: State<'_, Registry>, args: SpecArgs) -> Result<builder::SpecSql, String> {
+    let pool = &db::pool_of(&reg, &args.conn_id).await.map_err(|e| e.to_string())?;
+    builder::preview_spec(&args.spec, pool.dialect()).map_err(|e| e.to_string())
+}
+
+#[derive(Deserialize)]
+pub struct InsertSpecArgs {
+    pub conn_id: String,
+    pub spec: builder::InsertSpec,
+    pub session_id: Option<String>,
+}
+
+/// Insert one row from the grid; the result carries `rows_affected` and `last_insert_id`.
+#[tauri::command]
+pub async fn execute_insert_spec(
+    reg: State<'_, Registry>,
+    args: InsertSpecArgs,
+) -> Result<QueryResult, String> {
+    edit::insert(&reg, &args.conn_id, args.session_id.as_deref(), &args.spec)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[derive(Deserialize)]
+pub struct BulkInsertArgs {
+    pub conn_id: String,
+    pub spec: builder::BulkInsertSpec,
+    pub session_id: Option<String>,
+}
+
+/// Insert many rows at once, or upsert them when `spec.upsert` is set.
+#[tauri::command]
+pub async fn execute_bulk_insert(
+    reg: State<'_, Registry>,
+    args: BulkInsertArgs,
+) -> Result<QueryResult, String> {
+    edit::bulk_insert(&reg, &args.conn_id, args.session_id.as_deref(), &args.spec)
         .await
         .map_err(|e| e.to_string())
 }
 
+#[derive(Deserialize)]
+pub struct UpdateSpecArgs {
+    pub conn_id: String,
+    pub spec: builder::UpdateSpec,
+    pub session_id: Option<String>,
+    /// Allow filters that do not pin a single row by primary key.
+    #[serde(default)]
+    pub allow_unpinned: bool,
+}
+
+#[tauri::command]
+pub async fn execute_update_spec(
+    reg: State<'_, Registry>,
+    args: UpdateSpecArgs,
+) -> Result<QueryResult, String> {
+    edit::update(&reg, &args.conn_id, args.session_id.as_deref(), &args.spec, args.allow_unpinned)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[derive(Deserialize)]
+pub struct DeleteSpecArgs {
+    pub conn_id: String,
+    pub spec: builder::DeleteSpec,
+    pub session_id: Option<String>,
+    #[serde(default)]
+    pub allow_unpinned: bool,
+}
+
+#[tauri::command]
+pub async fn execute_delete_spec(
+    reg: State<'_, Registry>,
+    args: DeleteSpecArgs,
+) -> Result<QueryResult, String> {
+    edit::delete(&reg, &args.conn_id, args.session_id.as_deref(), &args.spec, args.allow_unpinned)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[derive(Deserialize)]
+pub struct ChangeSetArgs {
+    pub conn_id: String,
+    pub change_set: ChangeSet,
+}
+
+/// Validate grid edits and return the SQL they would run.
+#[tauri::command]
+pub async fn preview_change_set(
+    reg: State<'_, Registry>,
+    args: ChangeSetArgs,
+) -> Result<ChangeSetResult, String> {
+    changeset::preview(&reg, &args.conn_id, &args.change_set)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Apply grid edits in one transaction; `error` names the failing change.
+#[tauri::command]
+pub async fn apply_change_set(
+    reg: State<'_, Registry>,
+    args: ChangeSetArgs,
+) -> Result<ChangeSetResult, String> {
+    changeset::apply(&reg, &args.conn_id, &args.change_set)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[derive(Deserialize)]
+pub struct StreamSelectSpecArgs {
+    pub conn_id: String,
+    pub spec: builder::SelectSpec,
+    pub batch_size: Option<usize>,
+    pub query_id: Option<String>,
+    pub session_id: Option<String>,
+}
+
+/// Streaming variant of `execute_select_spec`; rows are pushed to `on_event`.
+#[tauri::command]
+pub async fn execute_select_spec_stream(
+    reg: State<'_, Registry>,
+    mut args: StreamSelectSpecArgs,
+    on_event: Channel<QueryEvent>,
+) -> Result<QueryResult, String> {
+    if args.spec.keyset.is_some() {
+        // pages are trimmed and reordered after fetching, which streaming cannot do
+        return Err("keyset pagination is not supported when streaming; use execute_select_spec".into());
+    }
+    let errors = db::check_spec(&reg, &args.conn_id, &mut args.spec)
+        .await
+        .map_err(|e| e.to_string())?;
+    validate::ensure(errors).map_err(|e| e.to_string())?;
+    let pool = &db::pool_of(&reg, &args.conn_id).await.map_err(|e| e.to_string())?;
+    let dialect = pool.dialect();
+    let (sql, values) = builder::build_select(&args.spec, dialect).map_err(|e| e.to_string())?;
+    let query_id = args.query_id.unwrap_or_else(db::new_query_id);
+    let tag = QueryTag { running: &reg.running, conn_id: &args.conn_id, query_id: &query_id };
+    let mut sink = batch_sink(on_event, args.batch_size, &query_id, Some(args.spec.clone()))?;
+    let res = match args.session_id.as_deref() {
+        Some(session_id) => {
+            session::run(&reg, &args.conn_id, session_id, &sql, Some(values), 1000, Some(&mut sink), Some(&query_id)).await
+        }
+        None => db::stream_sql_with_binds(pool, &sql, values, 1000, Some(&tag), &mut sink).await,
+    };
+    res.map(|r| r.with_sources(&args.spec))
+        .map_err(|e| e.to_string())
+}
+
 #[cfg(target_os = "macos")]
 fn start_security_scope(path: &str) -> Result<(), String> {
   unsafe {
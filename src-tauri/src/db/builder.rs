@@ -1,34 +1,245 @@
+use anyhow::{bail, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine as _;
 use sea_query::{
-    Alias, Expr, Order, Query, PostgresQueryBuilder, MysqlQueryBuilder, SqliteQueryBuilder,
-    SimpleExpr,
+    Alias, Asterisk, BinOper, Condition, DeleteStatement, Expr, Func, InsertStatement, IntoTableRef, JoinType, LikeExpr,
+    NullOrdering, Order, Query, PostgresQueryBuilder, MysqlQueryBuilder, QueryBuilder, QueryStatementWriter,
+    SelectStatement, SqliteQueryBuilder, SimpleExpr, TableRef, UpdateStatement, Value,
 };
 use sea_query_binder::{SqlxBinder, SqlxValues};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 
-use super::Dialect;
+use super::decode::f32_json;
+use super::schema::ForeignKeyDef;
+use super::{mark_source_table, ColumnMeta, Dialect};
 
 /// Specification for SELECT built from UI.
+/// With joins, columns, filters and sort may name `alias.column`; unqualified
+/// names refer to `table`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SelectSpec {
+    /// Postgres schema, MySQL database or attached SQLite database; the
+    /// connection's default when None.
+    #[serde(default)]
+    pub schema: Option<String>,
     pub table: String,
     pub columns: Vec<String>,
-    pub filters: Vec<FilterCond>,
-    pub sort: Option<(String, bool)>, // (col, asc)
+    pub filters: Vec<FilterNode>, // combined with AND
+    pub sort: Option<Sort>,
     pub limit: Option<u64>,
     pub offset: Option<u64>,
+    #[serde(default)]
+    pub joins: Vec<JoinSpec>,
+    /// Aggregate columns, selected after `columns`.
+    #[serde(default)]
+    pub aggregates: Vec<AggregateSpec>,
+    /// Selected as well when `columns` is empty.
+    #[serde(default)]
+    pub group_by: Vec<String>,
+    /// Conditions on groups; a condition's column may name an aggregate alias.
+    #[serde(default)]
+    pub having: Vec<FilterNode>,
+    /// Page by cursor instead of `offset`; see [`super::keyset`].
+    #[serde(default)]
+    pub keyset: Option<Keyset>,
+}
+
+/// Keyset pagination. Rows are ordered by `sort` with the primary key (or
+/// `unique_key`) appended, and each page starts from a cursor returned with
+/// the previous one.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Keyset {
+    /// `next` cursor of the previous page.
+    pub after: Option<String>,
+    /// `prev` cursor of the following page.
+    pub before: Option<String>,
+    /// Unique, NOT NULL columns to break ties with instead of the primary key.
+    #[serde(default)]
+    pub unique_key: Vec<String>,
+}
+
+/// ORDER BY: `[column, asc]` for a single key, or a list of keys.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Sort {
+    Single(String, bool),
+    Keys(Vec<SortKey>),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SortKey {
+    pub column: String,
+    #[serde(default = "default_asc")]
+    pub asc: bool,
+    /// Database default when None.
+    #[serde(default)]
+    pub nulls: Option<Nulls>,
+}
+
+fn default_asc() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Nulls {
+    First,
+    Last,
+}
+
+impl Sort {
+    pub fn keys(&self) -> Vec<SortKey> {
+        match self {
+            Sort::Single(column, asc) => vec![SortKey { column: column.clone(), asc: *asc, nulls: None }],
+            Sort::Keys(keys) => keys.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AggregateSpec {
+    pub func: AggregateFunc,
+    /// None counts rows (`COUNT(*)`); required for the other functions.
+    pub column: Option<String>,
+    /// Result column name; defaults to e.g. `sum_amount`, or `count` for `COUNT(*)`.
+    pub alias: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AggregateFunc {
+    Count,
+    CountDistinct,
+    Sum,
+    Avg,
+    Min,
+    Max,
+}
+
+impl AggregateSpec {
+    pub fn alias(&self) -> String {
+        if let Some(a) = &self.alias {
+            return a.clone();
+        }
+        let func = match self.func {
+            AggregateFunc::Count => "count",
+            AggregateFunc::CountDistinct => "count_distinct",
+            AggregateFunc::Sum => "sum",
+            AggregateFunc::Avg => "avg",
+            AggregateFunc::Min => "min",
+            AggregateFunc::Max => "max",
+        };
+        match &self.column {
+            Some(c) => format!("{}_{}", func, c.replace('.', "_")),
+            None => func.to_string(),
+        }
+    }
+
+    fn expr(&self, spec: &SelectSpec) -> SimpleExpr {
+        let arg = match &self.column {
+            Some(c) => {
+                let (alias, col) = spec.resolve(c);
+                Expr::col((Alias::new(alias), Alias::new(col)))
+            }
+            None => Expr::col(Asterisk),
+        };
+        match self.func {
+            AggregateFunc::Count => Func::count(arg).into(),
+            AggregateFunc::CountDistinct => Func::count_distinct(arg).into(),
+            AggregateFunc::Sum => Func::sum(arg).into(),
+            AggregateFunc::Avg => Func::avg(arg).into(),
+            AggregateFunc::Min => Func::min(arg).into(),
+            AggregateFunc::Max => Func::max(arg).into(),
+        }
+    }
+}
+
+/// Join along a discovered foreign key, from the table holding
+/// `foreign_key.columns` to `foreign_key.to_table`, matching every column pair.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JoinSpec {
+    #[serde(default)]
+    pub kind: JoinKind,
+    pub foreign_key: ForeignKeyDef,
+    /// Alias of the referencing side: the base table (default) or an earlier join.
+    pub from: Option<String>,
+    /// Alias for the joined table; defaults to its name.
+    pub alias: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JoinKind {
+    Inner,
+    #[default]
+    Left,
+}
+
+impl JoinSpec {
+    /// Name the joined table is referred to by.
+    pub fn alias(&self) -> &str {
+        self.alias.as_deref().unwrap_or(&self.foreign_key.to_table)
+    }
+}
+
+impl SelectSpec {
+    /// Split `alias.column` when the prefix names the base table or a join;
+    /// anything else is a column of the base table.
+    pub fn resolve<'a>(&'a self, name: &'a str) -> (&'a str, &'a str) {
+        if let Some((alias, col)) = name.split_once('.') {
+            if alias == self.table || self.joins.iter().any(|j| j.alias() == alias) {
+                return (alias, col);
+            }
+        }
+        (&self.table, name)
+    }
+
+    // Plain (non-aggregate) columns in select order.
+    fn plain_columns(&self) -> &[String] {
+        if self.columns.is_empty() { &self.group_by } else { &self.columns }
+    }
+
+    fn is_star(&self) -> bool {
+        self.columns.is_empty() && self.group_by.is_empty() && self.aggregates.is_empty()
+    }
+
+    // Table behind an alias.
+    fn table_of<'a>(&'a self, alias: &'a str) -> &'a str {
+        self.joins
+            .iter()
+            .find(|j| j.alias() == alias)
+            .map_or(alias, |j| j.foreign_key.to_table.as_str())
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FilterCond {
     pub column: String,
-    pub op: String,           // "=", "!=", ">", ">=", "<", "<=", "like", "not_like", "is_null", "is_not_null", "in", "not_in"
+    pub op: String,           // see `compare` for the supported operators
     pub value: JsonValue,     // numbers/strings/bools/arrays/null
 }
 
+/// A WHERE condition tree: `{"all": [...]}`, `{"any": [...]}`, `{"not": ...}`
+/// or a single condition, so a flat list of conditions is still accepted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum FilterNode {
+    All { all: Vec<FilterNode> },
+    Any { any: Vec<FilterNode> },
+    Not { not: Box<FilterNode> },
+    Cond(FilterCond),
+}
+
+impl From<FilterCond> for FilterNode {
+    fn from(c: FilterCond) -> Self {
+        FilterNode::Cond(c)
+    }
+}
+
 // ---- helpers ----
 
-fn json_to_simple(val: &JsonValue) -> SimpleExpr {
+pub fn json_to_simple(val: &JsonValue) -> SimpleExpr {
     match val {
         JsonValue::Null => Expr::val(Option::<i32>::None).into(),
         JsonValue::Bool(b) => Expr::val(*b).into(),
@@ -46,6 +257,14 @@ fn json_to_simple(val: &JsonValue) -> SimpleExpr {
     }
 }
 
+// `schema.table`, or just `table` without a schema.
+fn table_ref(schema: Option<&str>, table: &str) -> TableRef {
+    match schema.filter(|s| !s.is_empty()) {
+        Some(schema) => (Alias::new(schema), Alias::new(table)).into_table_ref(),
+        None => Alias::new(table).into_table_ref(),
+    }
+}
+
 fn json_array_to_simples(vals: &JsonValue) -> Vec<SimpleExpr> {
     match vals {
         JsonValue::Array(arr) => arr.iter().map(json_to_simple).collect(),
@@ -53,58 +272,243 @@ fn json_array_to_simples(vals: &JsonValue) -> Vec<SimpleExpr> {
     }
 }
 
+fn text(cond: &FilterCond) -> Result<&str> {
+    match cond.value.as_str() {
+        Some(s) => Ok(s),
+        None => bail!("operator {} needs a string value", cond.op),
+    }
+}
+
+// LIKE pattern matching `s` literally, with '!' as the escape character.
+fn like_literal(prefix: &str, s: &str, suffix: &str) -> LikeExpr {
+    let mut pattern = String::from(prefix);
+    for ch in s.chars() {
+        if matches!(ch, '!' | '%' | '_') {
+            pattern.push('!');
+        }
+        pattern.push(ch);
+    }
+    pattern.push_str(suffix);
+    LikeExpr::new(pattern).escape('!')
+}
+
+// The current time minus `days` days, in the database's clock.
+fn days_ago(cond: &FilterCond, dialect: Dialect) -> Result<SimpleExpr> {
+    let days = match cond.value.as_u64() {
+        Some(n) if n <= i32::MAX as u64 => n as i32,
+        _ => bail!("operator {} needs a non-negative whole number of days", cond.op),
+    };
+    Ok(match dialect {
+        Dialect::Postgres => Expr::cust_with_values("NOW() - INTERVAL '1 day' * $1", [days]),
+        Dialect::MySql    => Expr::cust_with_values("NOW() - INTERVAL ? DAY", [days]),
+        Dialect::Sqlite   => Expr::cust_with_values("datetime('now', ?)", [format!("-{} days", days)]),
+    })
+}
+
+/// Apply one condition's operator to `col`:
+/// `=`, `!=`, `>`, `>=`, `<`, `<=`, `like`, `not_like`, `ilike`, `not_ilike`,
+/// `contains` (case-insensitive), `starts_with`, `ends_with`, `regex`,
+/// `is_null`, `is_not_null`, `in`, `not_in`, `between`, `not_between`
+/// (value `[low, high]`), `in_last_days` and `older_than_days` (value N).
+/// SQLite `regex` needs the REGEXP function registered on the connection.
+fn compare(col: Expr, cond: &FilterCond, dialect: Dialect) -> Result<SimpleExpr> {
+    let value = &cond.value;
+    Ok(match cond.op.as_str() {
+        "="  => col.eq(json_to_simple(value)),
+        "!=" => col.ne(json_to_simple(value)),
+        ">"  => col.gt(json_to_simple(value)),
+        ">=" => col.gte(json_to_simple(value)),
+        "<"  => col.lt(json_to_simple(value)),
+        "<=" => col.lte(json_to_simple(value)),
+        "like" => col.like(text(cond)?),
+        "not_like" => col.not_like(text(cond)?),
+        "ilike" => Expr::expr(Func::lower(col)).like(text(cond)?.to_lowercase()),
+        "not_ilike" => Expr::expr(Func::lower(col)).not_like(text(cond)?.to_lowercase()),
+        "contains" => Expr::expr(Func::lower(col)).like(like_literal("%", &text(cond)?.to_lowercase(), "%")),
+        "starts_with" => col.like(like_literal("", text(cond)?, "%")),
+        "ends_with" => col.like(like_literal("%", text(cond)?, "")),
+        "regex" => {
+            let op = match dialect {
+                Dialect::Postgres => "~",
+                Dialect::MySql | Dialect::Sqlite => "REGEXP",
+            };
+            col.binary(BinOper::Custom(op), Expr::val(text(cond)?))
+        }
+        "is_null" => col.is_null(),
+        "is_not_null" => col.is_not_null(),
+        "in" => col.is_in(json_array_to_simples(value)),
+        "not_in" => col.is_not_in(json_array_to_simples(value)),
+        "between" | "not_between" => {
+            let Some([low, high]) = value.as_array().map(Vec::as_slice) else {
+                bail!("operator {} needs a [low, high] pair", cond.op);
+            };
+            if cond.op == "between" {
+                col.between(json_to_simple(low), json_to_simple(high))
+            } else {
+                col.not_between(json_to_simple(low), json_to_simple(high))
+            }
+        }
+        "in_last_days" => col.gte(days_ago(cond, dialect)?),
+        "older_than_days" => col.lt(days_ago(cond, dialect)?),
+        op => bail!("unknown filter operator: {}", op),
+    })
+}
+
 // ---- builders ----
 
-pub fn build_select(spec: &SelectSpec, dialect: Dialect) -> (String, SqlxValues) {
+pub fn build_select(spec: &SelectSpec, dialect: Dialect) -> Result<(String, SqlxValues)> {
+    let stmt = select_stmt(spec, dialect)?;
+    Ok(match dialect {
+        Dialect::Postgres => stmt.build_sqlx(PostgresQueryBuilder),
+        Dialect::MySql    => stmt.build_sqlx(MysqlQueryBuilder),
+        Dialect::Sqlite   => stmt.build_sqlx(SqliteQueryBuilder),
+    })
+}
+
+/// SQL of [`build_select`] with values inlined, for display only.
+pub fn preview_select(spec: &SelectSpec, dialect: Dialect) -> Result<String> {
+    Ok(inline(&select_stmt(spec, dialect)?, dialect))
+}
+
+/// `COUNT(*)` of the rows `spec` selects, ignoring sort and paging. Grouped
+/// specs count their groups.
+pub fn build_count(spec: &SelectSpec, dialect: Dialect) -> Result<(String, SqlxValues)> {
+    let mut all = spec.clone();
+    all.sort = None;
+    all.limit = None;
+    all.offset = None;
+    all.keyset = None;
+    let mut inner = select_stmt(&all, dialect)?;
+    let stmt = if all.group_by.is_empty() && all.aggregates.is_empty() {
+        inner.clear_selects().expr(Func::count(Expr::col(Asterisk))).to_owned()
+    } else {
+        Query::select()
+            .expr(Func::count(Expr::col(Asterisk)))
+            .from_subquery(inner, Alias::new("groups"))
+            .to_owned()
+    };
+    Ok(match dialect {
+        Dialect::Postgres => stmt.build_sqlx(PostgresQueryBuilder),
+        Dialect::MySql    => stmt.build_sqlx(MysqlQueryBuilder),
+        Dialect::Sqlite   => stmt.build_sqlx(SqliteQueryBuilder),
+    })
+}
+
+pub fn select_stmt(spec: &SelectSpec, dialect: Dialect) -> Result<SelectStatement> {
     let mut stmt = Query::select();
 
     // columns
-    if spec.columns.is_empty() {
+    if spec.is_star() {
         stmt.expr(Expr::cust("*"));
     } else {
-        for c in &spec.columns {
-            stmt.column((Alias::new(&spec.table), Alias::new(c)));
+        for c in spec.plain_columns() {
+            let (alias, col) = spec.resolve(c);
+            if alias == spec.table {
+                stmt.column((Alias::new(alias), Alias::new(col)));
+            } else {
+                // label joined columns `alias.column` so they stay distinct
+                stmt.expr_as(Expr::col((Alias::new(alias), Alias::new(col))), Alias::new(c));
+            }
         }
     }
 
-    stmt.from(Alias::new(&spec.table));
+    for a in &spec.aggregates {
+        stmt.expr_as(a.expr(spec), Alias::new(a.alias()));
+    }
+
+    stmt.from(table_ref(spec.schema.as_deref(), &spec.table));
 
-    // filters
-    for cond in &spec.filters {
-        let col = Expr::col((Alias::new(&spec.table), Alias::new(&cond.column)));
-        let expr = match cond.op.as_str() {
-            "="  => col.eq(json_to_simple(&cond.value)),
-            "!=" => col.ne(json_to_simple(&cond.value)),
-            ">"  => col.gt(json_to_simple(&cond.value)),
-            ">=" => col.gte(json_to_simple(&cond.value)),
-            "<"  => col.lt(json_to_simple(&cond.value)),
-            "<=" => col.lte(json_to_simple(&cond.value)),
-            "like" => col.like(cond.value.as_str().unwrap_or_default()),
-            "not_like" => col.not_like(cond.value.as_str().unwrap_or_default()),
-            "is_null" => col.is_null(),
-            "is_not_null" => col.is_not_null(),
-            "in" => col.is_in(json_array_to_simples(&cond.value)),
-            "not_in" => col.is_not_in(json_array_to_simples(&cond.value)),
-            _ => col.eq(json_to_simple(&cond.value)),
+    // joins
+    for j in &spec.joins {
+        let fk = &j.foreign_key;
+        let from = j.from.as_deref().unwrap_or(&spec.table);
+        if fk.columns.is_empty() || fk.columns.len() != fk.ref_columns.len() {
+            bail!("foreign key to {} needs matching columns and ref_columns", fk.to_table);
+        }
+        let mut on = Condition::all();
+        for (c, r) in fk.columns.iter().zip(&fk.ref_columns) {
+            on = on.add(Expr::col((Alias::new(from), Alias::new(c))).equals((Alias::new(j.alias()), Alias::new(r))));
+        }
+        let kind = match j.kind {
+            JoinKind::Inner => JoinType::InnerJoin,
+            JoinKind::Left => JoinType::LeftJoin,
         };
-        stmt.and_where(expr);
+        stmt.join_as(kind, table_ref(fk.ref_schema.as_deref(), &fk.to_table), Alias::new(j.alias()), on);
+    }
+
+    // filters
+    if !spec.filters.is_empty() {
+        stmt.cond_where(group(spec, Condition::all(), &spec.filters, false, dialect)?);
+    }
+
+    // grouping
+    for g in &spec.group_by {
+        let (alias, col) = spec.resolve(g);
+        stmt.group_by_col((Alias::new(alias), Alias::new(col)));
+    }
+    if !spec.having.is_empty() {
+        stmt.cond_having(group(spec, Condition::all(), &spec.having, true, dialect)?);
     }
 
     // sort
-    if let Some((col, asc)) = &spec.sort {
-        stmt.order_by(
-            (Alias::new(&spec.table), Alias::new(col)),
-            if *asc { Order::Asc } else { Order::Desc },
-        );
+    for key in spec.sort.iter().flat_map(Sort::keys) {
+        let (alias, col) = spec.resolve(&key.column);
+        let col = (Alias::new(alias), Alias::new(col));
+        let order = if key.asc { Order::Asc } else { Order::Desc };
+        match key.nulls {
+            Some(Nulls::First) => stmt.order_by_with_nulls(col, order, NullOrdering::First),
+            Some(Nulls::Last) => stmt.order_by_with_nulls(col, order, NullOrdering::Last),
+            None => stmt.order_by(col, order),
+        };
     }
 
     if let Some(lim) = spec.limit { stmt.limit(lim); }
     if let Some(off) = spec.offset { stmt.offset(off); }
 
-    match dialect {
-        Dialect::Postgres => stmt.build_sqlx(PostgresQueryBuilder),
-        Dialect::MySql    => stmt.build_sqlx(MysqlQueryBuilder),
-        Dialect::Sqlite   => stmt.build_sqlx(SqliteQueryBuilder),
+    Ok(stmt)
+}
+
+fn group(spec: &SelectSpec, cond: Condition, nodes: &[FilterNode], having: bool, dialect: Dialect) -> Result<Condition> {
+    nodes.iter().try_fold(cond, |c, n| Ok(c.add(condition(spec, n, having, dialect)?)))
+}
+
+// In HAVING, a condition may name an aggregate by its alias; the aggregate
+// expression itself is repeated since Postgres does not accept the alias.
+fn condition(spec: &SelectSpec, node: &FilterNode, having: bool, dialect: Dialect) -> Result<Condition> {
+    match node {
+        FilterNode::All { all } => group(spec, Condition::all(), all, having, dialect),
+        FilterNode::Any { any } => group(spec, Condition::any(), any, having, dialect),
+        FilterNode::Not { not } => Ok(condition(spec, not, having, dialect)?.not()),
+        FilterNode::Cond(cond) => {
+            let aggregate = having
+                .then(|| spec.aggregates.iter().find(|a| a.alias() == cond.column))
+                .flatten();
+            let col = match aggregate {
+                Some(a) => Expr::expr(a.expr(spec)),
+                None => {
+                    let (alias, column) = spec.resolve(&cond.column);
+                    Expr::col((Alias::new(alias), Alias::new(column)))
+                }
+            };
+            Ok(Condition::all().add(compare(col, cond, dialect)?))
+        }
+    }
+}
+
+/// Fill in the source table and column of a builder query's result columns.
+/// `SELECT *` over joins is left unmarked, as its columns are not known here.
+/// Aggregate columns have no single source and are left unmarked.
+pub fn mark_sources(meta: &mut [ColumnMeta], spec: &SelectSpec) {
+    if spec.is_star() {
+        if spec.joins.is_empty() {
+            mark_source_table(meta, &spec.table);
+        }
+        return;
+    }
+    for (m, c) in meta.iter_mut().zip(spec.plain_columns()) {
+        let (alias, col) = spec.resolve(c);
+        m.table = Some(spec.table_of(alias).to_string());
+        m.column = Some(col.to_string());
     }
 }
 
@@ -112,36 +516,172 @@ pub fn build_select(spec: &SelectSpec, dialect: Dialect) -> (String, SqlxValues)
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InsertSpec {
+    #[serde(default)]
+    pub schema: Option<String>,
     pub table: String,
     pub values: Vec<(String, JsonValue)>, // column -> value
 }
 
-pub fn build_insert(spec: &InsertSpec, dialect: Dialect) -> (String, SqlxValues) {
+pub fn build_insert(spec: &InsertSpec, dialect: Dialect) -> Result<(String, SqlxValues)> {
+    let stmt = insert_stmt(spec)?;
+    Ok(match dialect {
+        Dialect::Postgres => stmt.build_sqlx(PostgresQueryBuilder),
+        Dialect::MySql    => stmt.build_sqlx(MysqlQueryBuilder),
+        Dialect::Sqlite   => stmt.build_sqlx(SqliteQueryBuilder),
+    })
+}
+
+/// SQL of [`build_insert`] with values inlined, for display only.
+pub fn preview_insert(spec: &InsertSpec, dialect: Dialect) -> Result<String> {
+    Ok(inline(&insert_stmt(spec)?, dialect))
+}
+
+fn insert_stmt(spec: &InsertSpec) -> Result<InsertStatement> {
     let mut stmt = sea_query::Query::insert();
-    stmt.into_table(Alias::new(&spec.table));
+    stmt.into_table(table_ref(spec.schema.as_deref(), &spec.table));
 
     let cols: Vec<_> = spec.values.iter().map(|(c, _)| Alias::new(c)).collect();
     let vals: Vec<SimpleExpr> = spec.values.iter().map(|(_, v)| json_to_simple(v)).collect();
 
-    stmt.columns(cols).values_panic(vals);
+    stmt.columns(cols).values(vals)?;
+    Ok(stmt)
+}
+
+/// Many rows into one table, optionally as an upsert. `rows` hold values in
+/// `columns` order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BulkInsertSpec {
+    #[serde(default)]
+    pub schema: Option<String>,
+    pub table: String,
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<JsonValue>>,
+    #[serde(default)]
+    pub upsert: Option<Upsert>,
+}
 
+/// Update rows that collide with an inserted one instead of failing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Upsert {
+    /// Primary key or unique columns the conflict is detected on. MySQL
+    /// reacts to any unique key, so there they only pick the default `update`.
+    pub conflict: Vec<String>,
+    /// Columns overwritten with the new values; default: every inserted
+    /// column outside `conflict`. With none the colliding rows are kept as is.
+    #[serde(default)]
+    pub update: Vec<String>,
+}
+
+/// Most bind parameters one statement may carry. SQLite allows 32766 since
+/// 3.32 and 999 before, or less when built with a lower
+/// SQLITE_MAX_VARIABLE_NUMBER; sqlx 0.7 always links its bundled library
+/// (3.44, with the default limit), which `bulk_insert_fills_sqlite_bind_limit`
+/// checks. Use 999 here if the build ever moves to a system SQLite.
+fn max_binds(dialect: Dialect) -> usize {
     match dialect {
-        Dialect::Postgres => stmt.build_sqlx(PostgresQueryBuilder),
-        Dialect::MySql    => stmt.build_sqlx(MysqlQueryBuilder),
-        Dialect::Sqlite   => stmt.build_sqlx(SqliteQueryBuilder),
+        Dialect::Sqlite => 32766,
+        Dialect::Postgres | Dialect::MySql => 65535,
+    }
+}
+
+/// One INSERT per chunk of rows, each under the driver's bind limit.
+pub fn build_bulk_insert(spec: &BulkInsertSpec, dialect: Dialect) -> Result<Vec<(String, SqlxValues)>> {
+    Ok(bulk_insert_stmts(spec, dialect)?
+        .into_iter()
+        .map(|stmt| match dialect {
+            Dialect::Postgres => stmt.build_sqlx(PostgresQueryBuilder),
+            Dialect::MySql    => stmt.build_sqlx(MysqlQueryBuilder),
+            Dialect::Sqlite   => stmt.build_sqlx(SqliteQueryBuilder),
+        })
+        .collect())
+}
+
+/// SQL of [`build_bulk_insert`] with values inlined, for display only.
+pub fn preview_bulk_insert(spec: &BulkInsertSpec, dialect: Dialect) -> Result<Vec<String>> {
+    Ok(bulk_insert_stmts(spec, dialect)?.iter().map(|s| inline(s, dialect)).collect())
+}
+
+fn bulk_insert_stmts(spec: &BulkInsertSpec, dialect: Dialect) -> Result<Vec<InsertStatement>> {
+    if spec.columns.is_empty() {
+        bail!("bulk insert needs at least one column");
+    }
+    if spec.rows.is_empty() {
+        bail!("bulk insert has no rows");
+    }
+    let per_chunk = max_binds(dialect) / spec.columns.len();
+    if per_chunk == 0 {
+        bail!("{} columns exceed the bind limit of one statement", spec.columns.len());
+    }
+
+    let on_conflict = spec.upsert.as_ref().map(|u| {
+        let update: Vec<&String> = if u.update.is_empty() {
+            spec.columns.iter().filter(|c| !u.conflict.contains(c)).collect()
+        } else {
+            u.update.iter().collect()
+        };
+        let mut oc = sea_query::OnConflict::columns(u.conflict.iter().map(Alias::new));
+        match (update.is_empty(), dialect) {
+            (false, _) => oc.update_columns(update.into_iter().map(Alias::new)),
+            // MySQL has no DO NOTHING; assigning a key column to itself is the idiom
+            (true, Dialect::MySql) => {
+                let c = u.conflict.first().unwrap_or(&spec.columns[0]);
+                oc.value(Alias::new(c), Expr::col(Alias::new(c)))
+            }
+            (true, _) => oc.do_nothing(),
+        };
+        oc
+    });
+
+    let mut stmts = Vec::new();
+    for (n, chunk) in spec.rows.chunks(per_chunk).enumerate() {
+        let mut stmt = sea_query::Query::insert();
+        stmt.into_table(table_ref(spec.schema.as_deref(), &spec.table))
+            .columns(spec.columns.iter().map(Alias::new));
+        for (i, row) in chunk.iter().enumerate() {
+            if row.len() != spec.columns.len() {
+                bail!(
+                    "rows[{}] has {} values for {} columns",
+                    n * per_chunk + i,
+                    row.len(),
+                    spec.columns.len()
+                );
+            }
+            stmt.values(row.iter().map(json_to_simple))?;
+        }
+        if let Some(oc) = &on_conflict {
+            stmt.on_conflict(oc.clone());
+        }
+        stmts.push(stmt);
     }
+    Ok(stmts)
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UpdateSpec {
+    #[serde(default)]
+    pub schema: Option<String>,
     pub table: String,
     pub values: Vec<(String, JsonValue)>, // column -> value
     pub filters: Vec<FilterCond>,
 }
 
-pub fn build_update(spec: &UpdateSpec, dialect: Dialect) -> (String, SqlxValues) {
+pub fn build_update(spec: &UpdateSpec, dialect: Dialect) -> Result<(String, SqlxValues)> {
+    let stmt = update_stmt(spec, dialect)?;
+    Ok(match dialect {
+        Dialect::Postgres => stmt.build_sqlx(PostgresQueryBuilder),
+        Dialect::MySql    => stmt.build_sqlx(MysqlQueryBuilder),
+        Dialect::Sqlite   => stmt.build_sqlx(SqliteQueryBuilder),
+    })
+}
+
+/// SQL of [`build_update`] with values inlined, for display only.
+pub fn preview_update(spec: &UpdateSpec, dialect: Dialect) -> Result<String> {
+    Ok(inline(&update_stmt(spec, dialect)?, dialect))
+}
+
+fn update_stmt(spec: &UpdateSpec, dialect: Dialect) -> Result<UpdateStatement> {
     let mut stmt = sea_query::Query::update();
-    stmt.table(Alias::new(&spec.table));
+    stmt.table(table_ref(spec.schema.as_deref(), &spec.table));
 
     for (c, v) in &spec.values {
         stmt.value(Alias::new(c), json_to_simple(v));
@@ -149,70 +689,167 @@ pub fn build_update(spec: &UpdateSpec, dialect: Dialect) -> (String, SqlxValues)
 
     for cond in &spec.filters {
         let col = Expr::col((Alias::new(&spec.table), Alias::new(&cond.column)));
-        let expr = match cond.op.as_str() {
-            "="  => col.eq(json_to_simple(&cond.value)),
-            "!=" => col.ne(json_to_simple(&cond.value)),
-            _    => col.eq(json_to_simple(&cond.value)),
-        };
-        stmt.and_where(expr);
-    }
-
-    match dialect {
-        Dialect::Postgres => stmt.build_sqlx(PostgresQueryBuilder),
-        Dialect::MySql    => stmt.build_sqlx(MysqlQueryBuilder),
-        Dialect::Sqlite   => stmt.build_sqlx(SqliteQueryBuilder),
+        stmt.and_where(compare(col, cond, dialect)?);
     }
+    Ok(stmt)
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DeleteSpec {
+    #[serde(default)]
+    pub schema: Option<String>,
     pub table: String,
     pub filters: Vec<FilterCond>,
 }
 
-pub fn build_delete(spec: &DeleteSpec, dialect: Dialect) -> (String, SqlxValues) {
+pub fn build_delete(spec: &DeleteSpec, dialect: Dialect) -> Result<(String, SqlxValues)> {
+    let stmt = delete_stmt(spec, dialect)?;
+    Ok(match dialect {
+        Dialect::Postgres => stmt.build_sqlx(PostgresQueryBuilder),
+        Dialect::MySql    => stmt.build_sqlx(MysqlQueryBuilder),
+        Dialect::Sqlite   => stmt.build_sqlx(SqliteQueryBuilder),
+    })
+}
+
+/// SQL of [`build_delete`] with values inlined, for display only.
+pub fn preview_delete(spec: &DeleteSpec, dialect: Dialect) -> Result<String> {
+    Ok(inline(&delete_stmt(spec, dialect)?, dialect))
+}
+
+fn delete_stmt(spec: &DeleteSpec, dialect: Dialect) -> Result<DeleteStatement> {
     let mut stmt = sea_query::Query::delete();
-    stmt.from_table(Alias::new(&spec.table));
+    stmt.from_table(table_ref(spec.schema.as_deref(), &spec.table));
 
     for cond in &spec.filters {
         let col = Expr::col((Alias::new(&spec.table), Alias::new(&cond.column)));
-        let expr = match cond.op.as_str() {
-            "="  => col.eq(json_to_simple(&cond.value)),
-            "!=" => col.ne(json_to_simple(&cond.value)),
-            _    => col.eq(json_to_simple(&cond.value)),
-        };
-        stmt.and_where(expr);
+        stmt.and_where(compare(col, cond, dialect)?);
     }
+    Ok(stmt)
+}
 
+fn inline<S: QueryStatementWriter>(stmt: &S, dialect: Dialect) -> String {
     match dialect {
-        Dialect::Postgres => stmt.build_sqlx(PostgresQueryBuilder),
-        Dialect::MySql    => stmt.build_sqlx(MysqlQueryBuilder),
-        Dialect::Sqlite   => stmt.build_sqlx(SqliteQueryBuilder),
+        Dialect::Postgres => stmt.to_string(PostgresQueryBuilder),
+        Dialect::MySql    => stmt.to_string(MysqlQueryBuilder),
+        Dialect::Sqlite   => stmt.to_string(SqliteQueryBuilder),
     }
 }
 
+/// Any builder spec, tagged by `kind`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Spec {
+    Select(Box<SelectSpec>),
+    Insert(InsertSpec),
+    #[serde(rename = "bulk_insert")]
+    BulkInsert(BulkInsertSpec),
+    Update(UpdateSpec),
+    Delete(DeleteSpec),
+}
+
+/// The SQL a spec runs, for display.
+#[derive(Serialize, Debug)]
+pub struct SpecSql {
+    /// Values inlined as quoted literals; for reading, not for running.
+    pub sql: String,
+    /// The statement as sent, with placeholders.
+    pub parameterized: String,
+    /// Values bound to the placeholders, in order.
+    pub binds: Vec<JsonValue>,
+}
+
+/// SQL a spec runs. A bulk insert lists its chunks separated by `;`.
+pub fn preview_spec(spec: &Spec, dialect: Dialect) -> Result<SpecSql> {
+    let (sql, (parameterized, values)) = match spec {
+        Spec::Select(s) => (preview_select(s, dialect)?, build_select(s, dialect)?),
+        Spec::Insert(s) => (preview_insert(s, dialect)?, build_insert(s, dialect)?),
+        Spec::BulkInsert(s) => {
+            let built = build_bulk_insert(s, dialect)?;
+            let parameterized: Vec<&str> = built.iter().map(|(sql, _)| sql.as_str()).collect();
+            let binds = built.iter().flat_map(|(_, v)| &v.0 .0).map(|v| bind_to_json(v, dialect)).collect();
+            return Ok(SpecSql {
+                sql: preview_bulk_insert(s, dialect)?.join(";\n"),
+                parameterized: parameterized.join(";\n"),
+                binds,
+            });
+        }
+        Spec::Update(s) => (preview_update(s, dialect)?, build_update(s, dialect)?),
+        Spec::Delete(s) => (preview_delete(s, dialect)?, build_delete(s, dialect)?),
+    };
+    let binds = values.0 .0.iter().map(|v| bind_to_json(v, dialect)).collect();
+    Ok(SpecSql { sql, parameterized, binds })
+}
+
+// Bound values as JSON; binary as base64 like result cells, and anything
+// without a JSON counterpart as its SQL literal.
+fn bind_to_json(v: &Value, dialect: Dialect) -> JsonValue {
+    #[allow(unreachable_patterns)]
+    match v {
+        Value::Bool(b) => (*b).into(),
+        Value::TinyInt(n) => (*n).into(),
+        Value::SmallInt(n) => (*n).into(),
+        Value::Int(n) => (*n).into(),
+        Value::BigInt(n) => (*n).into(),
+        Value::TinyUnsigned(n) => (*n).into(),
+        Value::SmallUnsigned(n) => (*n).into(),
+        Value::Unsigned(n) => (*n).into(),
+        Value::BigUnsigned(n) => (*n).into(),
+        Value::Float(f) => f.map(f32_json).into(),
+        Value::Double(f) => (*f).into(),
+        Value::String(s) => s.as_deref().cloned().into(),
+        Value::Char(c) => c.map(String::from).into(),
+        Value::Bytes(b) => b.as_deref().map(|b| STANDARD.encode(b)).into(),
+        other => match dialect {
+            Dialect::Postgres => PostgresQueryBuilder.value_to_string(other),
+            Dialect::MySql    => MysqlQueryBuilder.value_to_string(other),
+            Dialect::Sqlite   => SqliteQueryBuilder.value_to_string(other),
+        }
+        .into(),
+    }
+}
+
+/// Whether `filters` pin a single row: every primary key column has an `=`
+/// condition with a non-null scalar value. False when there is no primary key.
+pub fn pins_primary_key(filters: &[FilterCond], pk: &[String]) -> bool {
+    !pk.is_empty()
+        && pk.iter().all(|k| {
+            filters.iter().any(|f| {
+                &f.column == k
+                    && f.op == "="
+                    && !matches!(f.value, JsonValue::Null | JsonValue::Array(_) | JsonValue::Object(_))
+            })
+        })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::db::Dialect;
+    use crate::db::{execute_sql, execute_sql_with_binds, pool_of, DynPool, TempDb};
 
     #[test]
     fn build_select_basic_sqlite_sql_shape() {
         // This test verifies that the SQL string produced by `build_select`
         // contains the basic expected clauses when using SQLite dialect.
         let spec = SelectSpec {
+            schema: None,
             table: "users".into(),
             columns: vec!["id".into(), "name".into()],
             filters: vec![
-                FilterCond { column: "active".into(), op: "=".into(), value: JsonValue::Bool(true) },
-                FilterCond { column: "name".into(), op: "like".into(), value: JsonValue::String("%a%".into()) },
+                FilterCond { column: "active".into(), op: "=".into(), value: JsonValue::Bool(true) }.into(),
+                FilterCond { column: "name".into(), op: "like".into(), value: JsonValue::String("%a%".into()) }.into(),
             ],
-            sort: Some(("id".into(), true)),
+            sort: Some(Sort::Single("id".into(), true)),
             limit: Some(50),
             offset: Some(10),
+            joins: vec![],
+            aggregates: vec![],
+            group_by: vec![],
+            having: vec![],
+            keyset: None,
         };
 
-        let (sql, _values) = super::build_select(&spec, Dialect::Sqlite);
+        let (sql, _values) = super::build_select(&spec, Dialect::Sqlite).unwrap();
         // Perform simple shape assertions on the generated SQL
         assert!(sql.to_lowercase().contains("select"));
         assert!(sql.contains("users"));
@@ -220,4 +857,352 @@ mod tests {
         assert!(sql.to_lowercase().contains("limit"));
         assert!(sql.to_lowercase().contains("offset"));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn build_select_joins_along_foreign_keys() {
+        let fk = |from: &str, to_table: &str| ForeignKeyDef {
+            name: None,
+            columns: vec![from.into()],
+            ref_schema: None,
+            to_table: to_table.into(),
+            ref_columns: vec!["id".into()],
+            on_update: None,
+            on_delete: None,
+        };
+        let spec = SelectSpec {
+            schema: None,
+            table: "orders".into(),
+            columns: vec!["id".into(), "c.name".into(), "countries.code".into()],
+            filters: vec![FilterCond { column: "c.name".into(), op: "like".into(), value: "a%".into() }.into()],
+            sort: Some(Sort::Single("c.name".into(), true)),
+            limit: None,
+            offset: None,
+            joins: vec![
+                JoinSpec { kind: JoinKind::Inner, foreign_key: fk("customer_id", "customers"), from: None, alias: Some("c".into()) },
+                JoinSpec { kind: JoinKind::Left, foreign_key: fk("country_id", "countries"), from: Some("c".into()), alias: None },
+            ],
+            aggregates: vec![],
+            group_by: vec![],
+            having: vec![],
+            keyset: None,
+        };
+
+        let (sql, _) = build_select(&spec, Dialect::Postgres).unwrap();
+        assert_eq!(
+            sql,
+            r#"SELECT "orders"."id", "c"."name" AS "c.name", "countries"."code" AS "countries.code" FROM "orders" "#.to_string()
+                + r#"INNER JOIN "customers" AS "c" ON "orders"."customer_id" = "c"."id" "#
+                + r#"LEFT JOIN "countries" AS "countries" ON "c"."country_id" = "countries"."id" "#
+                + r#"WHERE "c"."name" LIKE $1 ORDER BY "c"."name" ASC"#
+        );
+        let (sql, _) = build_select(&spec, Dialect::MySql).unwrap();
+        assert!(sql.contains("INNER JOIN `customers` AS `c` ON `orders`.`customer_id` = `c`.`id`"));
+
+        let mut composite = spec.clone();
+        composite.joins.truncate(1);
+        composite.columns.truncate(2);
+        let key = &mut composite.joins[0].foreign_key;
+        key.columns.push("region".into());
+        key.ref_columns.push("region".into());
+        let (sql, _) = build_select(&composite, Dialect::Sqlite).unwrap();
+        assert!(
+            sql.contains(r#"ON "orders"."customer_id" = "c"."id" AND "orders"."region" = "c"."region""#),
+            "{sql}"
+        );
+        composite.joins[0].foreign_key.ref_columns.pop();
+        assert!(build_select(&composite, Dialect::Sqlite).is_err());
+
+        let mut meta: Vec<ColumnMeta> = ["id", "c.name", "countries.code"]
+            .iter()
+            .map(|n| ColumnMeta { name: n.to_string(), type_name: "TEXT".into(), nullable: None, table: None, column: None })
+            .collect();
+        mark_sources(&mut meta, &spec);
+        assert_eq!(meta[1].table.as_deref(), Some("customers"));
+        assert_eq!(meta[1].column.as_deref(), Some("name"));
+        assert_eq!(meta[2].table.as_deref(), Some("countries"));
+    }
+
+    #[test]
+    fn filter_groups_nest_and_flat_lists_still_parse() {
+        let flat: SelectSpec = serde_json::from_value(serde_json::json!({
+            "table": "t", "columns": [], "sort": null, "limit": null, "offset": null,
+            "filters": [{ "column": "a", "op": "=", "value": 1 }, { "column": "b", "op": "=", "value": 2 }]
+        }))
+        .unwrap();
+        let (sql, _) = build_select(&flat, Dialect::Sqlite).unwrap();
+        assert!(sql.ends_with(r#"WHERE "t"."a" = ? AND "t"."b" = ?"#), "{sql}");
+
+        let tree: SelectSpec = serde_json::from_value(serde_json::json!({
+            "table": "t", "columns": [], "sort": null, "limit": null, "offset": null,
+            "filters": [{ "any": [
+                { "column": "status", "op": "=", "value": "open" },
+                { "all": [
+                    { "column": "priority", "op": ">", "value": 3 },
+                    { "not": { "column": "owner", "op": "is_null", "value": null } }
+                ] }
+            ] }]
+        }))
+        .unwrap();
+        let (sql, values) = build_select(&tree, Dialect::Postgres).unwrap();
+        assert!(
+            sql.ends_with(r#"WHERE "t"."status" = $1 OR ("t"."priority" > $2 AND (NOT "t"."owner" IS NULL))"#),
+            "{sql}"
+        );
+        assert_eq!(values.0 .0.len(), 2);
+    }
+
+    #[test]
+    fn aggregates_group_by_and_having() {
+        let spec: SelectSpec = serde_json::from_value(serde_json::json!({
+            "table": "orders", "columns": [], "filters": [], "sort": null, "limit": null, "offset": null,
+            "group_by": ["category"],
+            "aggregates": [
+                { "func": "count", "column": null, "alias": null },
+                { "func": "count_distinct", "column": "customer_id", "alias": "customers" },
+                { "func": "sum", "column": "amount", "alias": null }
+            ],
+            "having": [{ "column": "count", "op": ">", "value": 1 }]
+        }))
+        .unwrap();
+
+        let (sql, _) = build_select(&spec, Dialect::Postgres).unwrap();
+        assert_eq!(
+            sql,
+            r#"SELECT "orders"."category", COUNT(*) AS "count", COUNT(DISTINCT "orders"."customer_id") AS "customers", "#.to_string()
+                + r#"SUM("orders"."amount") AS "sum_amount" FROM "orders" GROUP BY "orders"."category" HAVING COUNT(*) > $1"#
+        );
+
+        let mut meta: Vec<ColumnMeta> = ["category", "count", "customers", "sum_amount"]
+            .iter()
+            .map(|n| ColumnMeta { name: n.to_string(), type_name: "INT8".into(), nullable: None, table: None, column: None })
+            .collect();
+        mark_sources(&mut meta, &spec);
+        assert_eq!(meta[0].column.as_deref(), Some("category"));
+        assert_eq!(meta[1].table, None);
+    }
+
+    #[test]
+    fn sort_keys_with_nulls_ordering() {
+        let spec: SelectSpec = serde_json::from_value(serde_json::json!({
+            "table": "t", "columns": ["a"], "filters": [], "limit": null, "offset": null,
+            "sort": [{ "column": "a", "nulls": "last" }, { "column": "b", "asc": false }]
+        }))
+        .unwrap();
+        let (sql, _) = build_select(&spec, Dialect::Postgres).unwrap();
+        assert!(sql.ends_with(r#"ORDER BY "t"."a" ASC NULLS LAST, "t"."b" DESC"#), "{sql}");
+
+        // a single `[column, asc]` pair is still accepted
+        let single: SelectSpec = serde_json::from_value(serde_json::json!({
+            "table": "t", "columns": [], "filters": [], "limit": null, "offset": null, "sort": ["a", false]
+        }))
+        .unwrap();
+        let (sql, _) = build_select(&single, Dialect::Sqlite).unwrap();
+        assert!(sql.ends_with(r#"ORDER BY "t"."a" DESC"#), "{sql}");
+    }
+
+    #[test]
+    fn filter_operators_per_dialect() {
+        let cond = |op: &str, value: JsonValue| FilterCond { column: "c".into(), op: op.into(), value };
+        let sql = |c: FilterCond, dialect| {
+            let spec = SelectSpec {
+                schema: None,
+                table: "t".into(),
+                columns: vec![],
+                filters: vec![c.into()],
+                sort: None,
+                limit: None,
+                offset: None,
+                joins: vec![],
+                aggregates: vec![],
+                group_by: vec![],
+                having: vec![],
+                keyset: None,
+            };
+            build_select(&spec, dialect).map(|(sql, _)| sql.split(" WHERE ").nth(1).unwrap().to_string())
+        };
+
+        assert_eq!(sql(cond("between", serde_json::json!([1, 5])), Dialect::Postgres).unwrap(), r#""t"."c" BETWEEN $1 AND $2"#);
+        assert_eq!(sql(cond("contains", "50%".into()), Dialect::Sqlite).unwrap(), r#"LOWER("t"."c") LIKE ? ESCAPE '!'"#);
+        assert_eq!(sql(cond("starts_with", "a".into()), Dialect::MySql).unwrap(), "`t`.`c` LIKE ? ESCAPE '!'");
+        assert_eq!(sql(cond("regex", "^a".into()), Dialect::Postgres).unwrap(), r#""t"."c" ~ $1"#);
+        assert_eq!(sql(cond("regex", "^a".into()), Dialect::MySql).unwrap(), "`t`.`c` REGEXP ?");
+        assert_eq!(
+            sql(cond("in_last_days", 7.into()), Dialect::Postgres).unwrap(),
+            r#""t"."c" >= (NOW() - INTERVAL '1 day' * $1)"#
+        );
+        assert_eq!(sql(cond("older_than_days", 7.into()), Dialect::Sqlite).unwrap(), r#""t"."c" < (datetime('now', ?))"#);
+
+        assert!(sql(cond("between", 1.into()), Dialect::Sqlite).is_err());
+        assert!(sql(cond("in_last_days", (-1).into()), Dialect::Sqlite).is_err());
+        let err = sql(cond("equals", 1.into()), Dialect::Sqlite).unwrap_err();
+        assert_eq!(err.to_string(), "unknown filter operator: equals");
+    }
+
+    #[test]
+    fn like_literal_escapes_wildcards() {
+        let spec = DeleteSpec { schema: None, table: "t".into(), filters: vec![FilterCond { column: "c".into(), op: "ends_with".into(), value: "5%_!".into() }] };
+        assert_eq!(preview_delete(&spec, Dialect::Sqlite).unwrap(), r#"DELETE FROM "t" WHERE "t"."c" LIKE '%5!%!_!!' ESCAPE '!'"#);
+    }
+
+    #[test]
+    fn specs_qualify_tables_with_schema() {
+        let schema = Some("sales".to_string());
+        let select: SelectSpec = serde_json::from_value(serde_json::json!({
+            "schema": "sales", "table": "orders", "columns": ["id"], "filters": [], "sort": null, "limit": null, "offset": null
+        }))
+        .unwrap();
+        let (sql, _) = build_select(&select, Dialect::Postgres).unwrap();
+        assert_eq!(sql, r#"SELECT "orders"."id" FROM "sales"."orders""#);
+        let (sql, _) = build_select(&select, Dialect::MySql).unwrap();
+        assert_eq!(sql, "SELECT `orders`.`id` FROM `sales`.`orders`");
+
+        let insert = InsertSpec { schema: schema.clone(), table: "orders".into(), values: vec![("id".into(), 1.into())] };
+        assert_eq!(preview_insert(&insert, Dialect::Postgres).unwrap(), r#"INSERT INTO "sales"."orders" ("id") VALUES (1)"#);
+        let key = vec![FilterCond { column: "id".into(), op: "=".into(), value: 1.into() }];
+        let update = UpdateSpec { schema: schema.clone(), table: "orders".into(), values: vec![("n".into(), 2.into())], filters: key.clone() };
+        assert_eq!(
+            preview_update(&update, Dialect::MySql).unwrap(),
+            "UPDATE `sales`.`orders` SET `n` = 2 WHERE `orders`.`id` = 1"
+        );
+        let delete = DeleteSpec { schema, table: "orders".into(), filters: key };
+        assert_eq!(
+            preview_delete(&delete, Dialect::Sqlite).unwrap(),
+            r#"DELETE FROM "sales"."orders" WHERE "orders"."id" = 1"#
+        );
+    }
+
+    #[test]
+    fn preview_spec_inlines_quoted_values_and_lists_binds() {
+        let spec: Spec = serde_json::from_value(serde_json::json!({
+            "kind": "select", "table": "users", "columns": ["id"], "sort": null, "limit": 10, "offset": null,
+            "filters": [{ "column": "name", "op": "=", "value": "O'Brien" }, { "column": "age", "op": "between", "value": [18, 30.5] }]
+        }))
+        .unwrap();
+        let out = preview_spec(&spec, Dialect::Postgres).unwrap();
+        assert_eq!(
+            out.sql,
+            r#"SELECT "users"."id" FROM "users" WHERE "users"."name" = E'O\'Brien' AND ("users"."age" BETWEEN 18 AND 30.5) LIMIT 10"#
+        );
+        assert_eq!(
+            out.parameterized,
+            r#"SELECT "users"."id" FROM "users" WHERE "users"."name" = $1 AND ("users"."age" BETWEEN $2 AND $3) LIMIT $4"#
+        );
+        assert_eq!(out.binds, vec![serde_json::json!("O'Brien"), 18.into(), 30.5.into(), 10.into()]);
+
+        let out = preview_spec(&spec, Dialect::MySql).unwrap();
+        assert!(out.sql.contains(r"`users`.`name` = 'O\'Brien'"), "{}", out.sql);
+    }
+
+    #[test]
+    fn pins_primary_key_requires_equality_on_every_key_column() {
+        let eq = |c: &str, v: JsonValue| FilterCond { column: c.into(), op: "=".into(), value: v };
+        let pk = vec!["a".to_string(), "b".to_string()];
+
+        assert!(pins_primary_key(&[eq("a", 1.into()), eq("b", "x".into()), eq("c", 2.into())], &pk));
+        assert!(!pins_primary_key(&[eq("a", 1.into())], &pk));
+        assert!(!pins_primary_key(&[eq("a", 1.into()), eq("b", JsonValue::Null)], &pk));
+        let ne = FilterCond { column: "b".into(), op: "!=".into(), value: 1.into() };
+        assert!(!pins_primary_key(&[eq("a", 1.into()), ne], &pk));
+        assert!(!pins_primary_key(&[eq("a", 1.into())], &[]));
+    }
+
+    #[test]
+    fn count_ignores_paging_and_counts_groups() {
+        let spec: SelectSpec = serde_json::from_value(serde_json::json!({
+            "table": "orders", "columns": ["id"], "limit": 50, "offset": 100,
+            "filters": [{ "column": "status", "op": "=", "value": "paid" }],
+            "sort": [{ "column": "id", "asc": false }]
+        }))
+        .unwrap();
+        let (sql, values) = build_count(&spec, Dialect::Postgres).unwrap();
+        assert_eq!(sql, r#"SELECT COUNT(*) FROM "orders" WHERE "orders"."status" = $1"#);
+        assert_eq!(values.0 .0.len(), 1);
+
+        let grouped = SelectSpec { columns: vec![], group_by: vec!["status".into()], ..spec };
+        let (sql, _) = build_count(&grouped, Dialect::Sqlite).unwrap();
+        assert_eq!(
+            sql,
+            r#"SELECT COUNT(*) FROM (SELECT "orders"."status" FROM "orders" WHERE "orders"."status" = ? GROUP BY "orders"."status") AS "groups""#
+        );
+    }
+
+    #[test]
+    fn bulk_insert_chunks_and_upserts_per_dialect() {
+        let mut spec: BulkInsertSpec = serde_json::from_value(serde_json::json!({
+            "table": "users", "columns": ["id", "name"], "rows": [[1, "ann"], [2, "bob"]],
+            "upsert": { "conflict": ["id"] }
+        }))
+        .unwrap();
+        let pg = preview_bulk_insert(&spec, Dialect::Postgres).unwrap();
+        assert_eq!(
+            pg,
+            [r#"INSERT INTO "users" ("id", "name") VALUES (1, 'ann'), (2, 'bob') ON CONFLICT ("id") DO UPDATE SET "name" = "excluded"."name""#]
+        );
+        let my = preview_bulk_insert(&spec, Dialect::MySql).unwrap();
+        assert_eq!(
+            my,
+            ["INSERT INTO `users` (`id`, `name`) VALUES (1, 'ann'), (2, 'bob') ON DUPLICATE KEY UPDATE `name` = VALUES(`name`)"]
+        );
+
+        spec.columns = vec!["id".into()];
+        spec.rows = vec![vec![1.into()], vec![2.into()]];
+        let my = preview_bulk_insert(&spec, Dialect::MySql).unwrap();
+        assert!(my[0].ends_with("ON DUPLICATE KEY UPDATE `id` = `id`"), "{}", my[0]);
+        let lite = preview_bulk_insert(&spec, Dialect::Sqlite).unwrap();
+        assert!(lite[0].ends_with(r#"ON CONFLICT ("id") DO NOTHING"#), "{}", lite[0]);
+
+        spec.upsert = None;
+        spec.rows = (0..40_000).map(|i| vec![i.into()]).collect();
+        let built = build_bulk_insert(&spec, Dialect::Sqlite).unwrap();
+        assert_eq!(built.iter().map(|(_, v)| v.0 .0.len()).collect::<Vec<_>>(), [32766, 7234]);
+
+        spec.rows[1] = vec![];
+        assert_eq!(build_bulk_insert(&spec, Dialect::Postgres).err().unwrap().to_string(), "rows[1] has 0 values for 1 columns");
+    }
+
+    /// Text and date operators run on SQLite, with REGEXP registered by
+    /// `open_connection`.
+    #[tokio::test]
+    async fn filter_operators_run_sqlite() -> anyhow::Result<()> {
+        let db = TempDb::open("ops").await?;
+        execute_sql(&db.reg, &db.conn_id, "CREATE TABLE t (name TEXT, seen TEXT)", 10, None, None).await?;
+        execute_sql(
+            &db.reg,
+            &db.conn_id,
+            "INSERT INTO t VALUES ('Alpha_1', datetime('now', '-1 days')), ('alpha%2', datetime('now', '-30 days')), \
+             ('beta', NULL)",
+            10,
+            None,
+            None,
+        )
+        .await?;
+        let pool = pool_of(&db.reg, &db.conn_id).await?;
+
+        let names = |op: &str, value: serde_json::Value| {
+            let spec: SelectSpec = serde_json::from_value(serde_json::json!({
+                "table": "t", "columns": ["name"], "limit": null, "offset": null,
+                "filters": [{ "column": if op.ends_with("_days") { "seen" } else { "name" }, "op": op, "value": value }],
+                "sort": [{ "column": "seen", "asc": false, "nulls": "first" }]
+            }))
+            .unwrap();
+            build_select(&spec, Dialect::Sqlite)
+        };
+        async fn run(pool: &DynPool, built: anyhow::Result<(String, SqlxValues)>) -> anyhow::Result<Vec<serde_json::Value>> {
+            let (sql, values) = built?;
+            let r = execute_sql_with_binds(pool, &sql, values, 100, None).await?;
+            Ok(r.rows.into_iter().map(|row| row[0].clone()).collect())
+        }
+
+        assert_eq!(run(&pool, names("regex", serde_json::json!("^[a-z]+$"))).await?, vec![serde_json::json!("beta")]);
+        assert_eq!(run(&pool, names("contains", serde_json::json!("A%"))).await?, vec![serde_json::json!("alpha%2")]);
+        assert_eq!(run(&pool, names("starts_with", serde_json::json!("Alpha_"))).await?, vec![serde_json::json!("Alpha_1")]);
+        assert_eq!(run(&pool, names("in_last_days", serde_json::json!(7))).await?, vec![serde_json::json!("Alpha_1")]);
+        assert_eq!(run(&pool, names("older_than_days", serde_json::json!(7))).await?, vec![serde_json::json!("alpha%2")]);
+        assert_eq!(
+            run(&pool, names("ilike", serde_json::json!("ALPHA%"))).await?,
+            vec![serde_json::json!("Alpha_1"), serde_json::json!("alpha%2")]
+        );
+        assert!(names("approx", serde_json::json!(1)).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,318 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use sqlx::pool::PoolConnection;
+use sqlx::{Pool, Row};
+
+use super::{DynConn, DynPool};
+
+/// Queries currently running, keyed by query id.
+pub type RunningQueries = Arc<Mutex<HashMap<String, RunningQuery>>>;
+
+/// Identifies a query so it can be cancelled from another command while it runs.
+pub struct QueryTag<'a> {
+    pub running: &'a RunningQueries,
+    pub conn_id: &'a str,
+    pub query_id: &'a str,
+}
+
+/// Server-side handle used to abort a running query.
+#[derive(Clone)]
+pub enum CancelHandle {
+    /// Checked by a progress handler on the connection (sqlite3_interrupt equivalent).
+    Sqlite,
+    /// Backend pid of the connection, cancelled with `pg_cancel_backend`.
+    Postgres { pool: Pool<sqlx::Postgres>, backend_pid: i32 },
+    /// Connection id of the session, cancelled with `KILL QUERY`.
+    MySql { pool: Pool<sqlx::MySql>, connection_id: u64 },
+}
+
+/// A registered query; `cancelled` is set once `cancel_query` has been called.
+pub struct RunningQuery {
+    pub conn_id: String,
+    pub handle: CancelHandle,
+    pub cancelled: Arc<AtomicBool>,
+    /// Set once the query is done with its connection. A server-side cancel
+    /// is sent while holding this lock, so it cannot reach whatever runs next
+    /// on that connection.
+    pub finished: Arc<tokio::sync::Mutex<bool>>,
+}
+
+/// Registration guard; removes the query from the running map when dropped.
+pub struct Tracked {
+    running: RunningQueries,
+    query_id: String,
+    cancelled: Arc<AtomicBool>,
+    finished: Arc<tokio::sync::Mutex<bool>>,
+}
+
+impl Tracked {
+    // Query ids come from the client; a second query under a running id
+    // would take over its entry and lose it when the first one finishes.
+    fn register(tag: &QueryTag<'_>, handle: CancelHandle) -> Result<Self> {
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let finished = Arc::new(tokio::sync::Mutex::new(false));
+        let mut running = tag.running.lock().unwrap();
+        if running.contains_key(tag.query_id) {
+            anyhow::bail!("query id already in use");
+        }
+        running.insert(
+            tag.query_id.to_string(),
+            RunningQuery {
+                conn_id: tag.conn_id.to_string(),
+                handle,
+                cancelled: cancelled.clone(),
+                finished: finished.clone(),
+            },
+        );
+        Ok(Self {
+            running: tag.running.clone(),
+            query_id: tag.query_id.to_string(),
+            cancelled,
+            finished,
+        })
+    }
+
+    /// Whether `cancel_query` has been called for this query.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Replace the driver error of a cancelled query with a clear message.
+    pub fn finish<T>(tracked: Option<Self>, res: Result<T>) -> Result<T> {
+        match (tracked, res) {
+            (Some(t), Err(_)) if t.is_cancelled() => {
+                Err(anyhow::anyhow!("query cancelled"))
+            }
+            (_, res) => res,
+        }
+    }
+}
+
+impl Drop for Tracked {
+    fn drop(&mut self) {
+        if let Ok(mut finished) = self.finished.try_lock() {
+            *finished = true;
+        }
+        self.running.lock().unwrap().remove(&self.query_id);
+    }
+}
+
+/// Register a tagged query running on `conn` so `cancel_query` can reach it.
+pub async fn track(
+    tag: Option<&QueryTag<'_>>,
+    pool: &DynPool,
+    conn: &mut DynConn,
+) -> Result<Option<Tracked>> {
+    match (pool, conn) {
+        (_, DynConn::Sqlite(c)) => track_sqlite(tag, c).await,
+        (DynPool::Postgres(p), DynConn::Postgres(c)) => track_postgres(tag, p, c).await,
+        (DynPool::MySql(p), DynConn::MySql(c)) => track_mysql(tag, p, c).await,
+        _ => anyhow::bail!("connection does not belong to the pool"),
+    }
+}
+
+/// Fail if the query was cancelled after it was registered but before it was
+/// sent; a server-side cancel finds nothing to abort at that point.
+pub fn ensure_running(tracked: Option<&Tracked>) -> Result<()> {
+    if tracked.is_some_and(|t| t.is_cancelled()) {
+        anyhow::bail!("query cancelled");
+    }
+    Ok(())
+}
+
+/// Unregister the query and report a cancelled one as such. Waits for a
+/// cancel that is being sent, so call it before the connection goes back to
+/// the pool.
+pub async fn finish<T>(tracked: Option<Tracked>, conn: &mut DynConn, res: Result<T>) -> Result<T> {
+    if let Some(t) = &tracked {
+        *t.finished.lock().await = true;
+    }
+    match conn {
+        DynConn::Sqlite(c) => finish_sqlite(tracked, c, res).await,
+        _ => Tracked::finish(tracked, res),
+    }
+}
+
+// Install an interrupting progress handler on the connection and register the query.
+async fn track_sqlite(
+    tag: Option<&QueryTag<'_>>,
+    conn: &mut PoolConnection<sqlx::Sqlite>,
+) -> Result<Option<Tracked>> {
+    let Some(tag) = tag else { return Ok(None) };
+    let tracked = Tracked::register(tag, CancelHandle::Sqlite)?;
+    let flag = tracked.cancelled.clone();
+    conn.lock_handle()
+        .await?
+        .set_progress_handler(1_000, move || !flag.load(Ordering::Relaxed));
+    Ok(Some(tracked))
+}
+
+// Remove the progress handler before the connection goes back to the pool.
+async fn finish_sqlite<T>(
+    tracked: Option<Tracked>,
+    conn: &mut PoolConnection<sqlx::Sqlite>,
+    res: Result<T>,
+) -> Result<T> {
+    if tracked.is_some() {
+        conn.lock_handle().await?.remove_progress_handler();
+    }
+    Tracked::finish(tracked, res)
+}
+
+async fn track_postgres(
+    tag: Option<&QueryTag<'_>>,
+    pool: &Pool<sqlx::Postgres>,
+    conn: &mut PoolConnection<sqlx::Postgres>,
+) -> Result<Option<Tracked>> {
+    let Some(tag) = tag else { return Ok(None) };
+    let backend_pid: i32 = sqlx::query("SELECT pg_backend_pid()")
+        .fetch_one(&mut **conn)
+        .await?
+        .try_get(0)?;
+    let handle = CancelHandle::Postgres { pool: pool.clone(), backend_pid };
+    Ok(Some(Tracked::register(tag, handle)?))
+}
+
+async fn track_mysql(
+    tag: Option<&QueryTag<'_>>,
+    pool: &Pool<sqlx::MySql>,
+    conn: &mut PoolConnection<sqlx::MySql>,
+) -> Result<Option<Tracked>> {
+    let Some(tag) = tag else { return Ok(None) };
+    let connection_id: u64 = sqlx::query("SELECT CONNECTION_ID()")
+        .fetch_one(&mut **conn)
+        .await?
+        .try_get(0)?;
+    let handle = CancelHandle::MySql { pool: pool.clone(), connection_id };
+    Ok(Some(Tracked::register(tag, handle)?))
+}
+
+/// Id for a query run on behalf of `query_id`, such as the row count next to
+/// a page; cancelling `query_id` aborts it as well.
+pub fn companion_id(query_id: &str, part: &str) -> String {
+    format!("{query_id}#{part}")
+}
+
+/// Abort a running query and its companions. Returns false if none of them
+/// is running.
+pub async fn cancel_query(running: &RunningQueries, query_id: &str) -> Result<bool> {
+    let prefix = companion_id(query_id, "");
+    cancel_where(running, |id, _| id == query_id || id.starts_with(&prefix)).await
+}
+
+// Flag every matching query, then send the server-side cancels. A failing
+// cancel does not stop the others; the failures are reported together.
+async fn cancel_where(
+    running: &RunningQueries,
+    matches: impl Fn(&str, &RunningQuery) -> bool,
+) -> Result<bool> {
+    let queries: Vec<_> = running
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(id, q)| matches(id, q))
+        .map(|(id, q)| {
+            q.cancelled.store(true, Ordering::SeqCst);
+            (id.clone(), q.handle.clone(), q.finished.clone())
+        })
+        .collect();
+    let mut errors = Vec::new();
+    for (id, handle, finished) in &queries {
+        let finished = finished.lock().await;
+        if *finished {
+            continue;
+        }
+        if let Err(e) = cancel_handle(handle).await {
+            errors.push(format!("{id}: {e}"));
+        }
+    }
+    if !errors.is_empty() {
+        anyhow::bail!("could not cancel {}", errors.join("; "));
+    }
+    Ok(!queries.is_empty())
+}
+
+async fn cancel_handle(handle: &CancelHandle) -> Result<()> {
+    match handle {
+        CancelHandle::Sqlite => {}
+        CancelHandle::Postgres { pool, backend_pid } => {
+            sqlx::query("SELECT pg_cancel_backend($1)")
+                .bind(*backend_pid)
+                .execute(pool)
+                .await?;
+        }
+        CancelHandle::MySql { pool, connection_id } => {
+            // KILL does not accept bind parameters; the id is numeric.
+            sqlx::query(&format!("KILL QUERY {}", connection_id))
+                .execute(pool)
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Cancel every query running on the given connection (used when it is closed).
+pub async fn cancel_all(running: &RunningQueries, conn_id: &str) -> Result<()> {
+    cancel_where(running, |_, q| q.conn_id == conn_id).await.map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn running_query_id_cannot_be_reused() {
+        let running = RunningQueries::default();
+        let tag = QueryTag { running: &running, conn_id: "c", query_id: "q" };
+        let first = Tracked::register(&tag, CancelHandle::Sqlite).unwrap();
+        let err = Tracked::register(&tag, CancelHandle::Sqlite).err().unwrap();
+        assert_eq!(err.to_string(), "query id already in use");
+        assert!(running.lock().unwrap().contains_key("q"));
+
+        drop(first);
+        assert!(running.lock().unwrap().is_empty());
+        assert!(Tracked::register(&tag, CancelHandle::Sqlite).is_ok());
+    }
+
+    #[tokio::test]
+    async fn cancelling_a_query_reaches_its_companions() -> Result<()> {
+        let running = RunningQueries::default();
+        let count_id = companion_id("q", "count");
+        let tag = |query_id| QueryTag { running: &running, conn_id: "c", query_id };
+        let count = Tracked::register(&tag(&count_id), CancelHandle::Sqlite)?;
+        let other = Tracked::register(&tag("q2"), CancelHandle::Sqlite)?;
+
+        // the page itself has finished; its count is still running
+        assert!(cancel_query(&running, "q").await?);
+        assert!(count.is_cancelled());
+        assert!(ensure_running(Some(&count)).is_err());
+        assert!(!other.is_cancelled());
+        Ok(())
+    }
+
+    /// Every server-side cancel is attempted, and none is sent for a query
+    /// that is already done with its connection.
+    #[tokio::test]
+    async fn cancels_are_sent_to_running_queries_only() -> Result<()> {
+        let running = RunningQueries::default();
+        let unreachable = || -> Result<CancelHandle> {
+            let pool = sqlx::postgres::PgPoolOptions::new()
+                .acquire_timeout(std::time::Duration::from_secs(2))
+                .connect_lazy("postgres://127.0.0.1:1/none")?;
+            Ok(CancelHandle::Postgres { pool, backend_pid: 1 })
+        };
+        let tag = |query_id| QueryTag { running: &running, conn_id: "c", query_id };
+        let _page = Tracked::register(&tag("q"), unreachable()?)?;
+        let _count = Tracked::register(&tag("q#count"), unreachable()?)?;
+        let err = cancel_query(&running, "q").await.err().unwrap().to_string();
+        assert!(err.contains("q: ") && err.contains("q#count: "), "{err}");
+
+        let done = Tracked::register(&tag("done"), unreachable()?)?;
+        *done.finished.lock().await = true;
+        assert!(cancel_query(&running, "done").await?);
+        Ok(())
+    }
+}
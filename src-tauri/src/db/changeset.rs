@@ -0,0 +1,307 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+use super::builder::{self, DeleteSpec, FilterCond, InsertSpec, UpdateSpec};
+use super::schema::{ColumnDef, DatabaseSchema, TableDef};
+use super::validate::{coerce, column, find_table};
+use super::{cached_schema, pool_of, refresh_schema, Dialect, Registry};
+
+/// Edits accumulated in the grid for one table, applied as a unit.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChangeSet {
+    #[serde(default)]
+    pub schema: Option<String>,
+    pub table: String,
+    pub changes: Vec<RowChange>,
+}
+
+/// One row-level edit. `key` holds the primary key values of the row as it
+/// was loaded; `values` are column -> new value pairs.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum RowChange {
+    Insert { values: Vec<(String, JsonValue)> },
+    Update { key: Vec<(String, JsonValue)>, values: Vec<(String, JsonValue)> },
+    Delete { key: Vec<(String, JsonValue)> },
+}
+
+/// Where a change set failed: the index into `changes` and, when the
+/// problem is with one cell, its column.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct ChangeError {
+    pub index: usize,
+    pub column: Option<String>,
+    pub message: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ChangeSetResult {
+    /// One statement per change with values inlined; empty if validation failed.
+    pub sql: Vec<String>,
+    /// True once the transaction has been committed.
+    pub applied: bool,
+    pub rows_affected: u64,
+    pub error: Option<ChangeError>,
+}
+
+enum Planned {
+    Insert(InsertSpec),
+    Update(UpdateSpec),
+    Delete(DeleteSpec),
+}
+
+/// Validate a change set and return the SQL it would run, without running it.
+pub async fn preview(reg: &Registry, conn_id: &str, set: &ChangeSet) -> Result<ChangeSetResult> {
+    let pool = pool_of(reg, conn_id).await?;
+    let dialect = pool.dialect();
+    let db = schema_for(reg, conn_id, set).await?;
+    Ok(match plan(&db.tables, set) {
+        Ok(planned) => ChangeSetResult {
+            sql: planned.iter().map(|p| p.preview(dialect)).collect::<Result<_>>()?,
+            applied: false,
+            rows_affected: 0,
+            error: None,
+        },
+        Err(e) => failed(Vec::new(), e),
+    })
+}
+
+/// Validate a change set and apply it in a single transaction. Any failing
+/// change, or an update/delete whose row no longer exists, rolls back the set.
+pub async fn apply(reg: &Registry, conn_id: &str, set: &ChangeSet) -> Result<ChangeSetResult> {
+    let pool = pool_of(reg, conn_id).await?;
+    let dialect = pool.dialect();
+    let db = schema_for(reg, conn_id, set).await?;
+    let planned = match plan(&db.tables, set) {
+        Ok(p) => p,
+        Err(e) => return Ok(failed(Vec::new(), e)),
+    };
+    let sql: Vec<String> = planned.iter().map(|p| p.preview(dialect)).collect::<Result<_>>()?;
+    let built = planned.iter().map(|p| p.build(dialect)).collect::<Result<Vec<_>>>()?;
+
+    let mut conn = pool.acquire().await?;
+    conn.begin().await?;
+
+    let mut rows_affected = 0;
+    for (index, (stmt, values)) in built.into_iter().enumerate() {
+        let error = match conn.run(&stmt, Some(values), 0, None).await {
+            Ok(r) => {
+                let n = r.rows_affected.unwrap_or(0);
+                rows_affected += n;
+                (n == 0).then(|| "row not found; it may have been changed or deleted".to_string())
+            }
+            Err(e) => Some(e.to_string()),
+        };
+        if let Some(message) = error {
+            conn.execute("ROLLBACK").await?;
+            return Ok(failed(sql, ChangeError { index, column: None, message }));
+        }
+    }
+    conn.execute("COMMIT").await?;
+
+    Ok(ChangeSetResult { sql, applied: true, rows_affected, error: None })
+}
+
+// The cached schema, introspected again once when the table is missing from
+// it, as it may have been created since.
+async fn schema_for(reg: &Registry, conn_id: &str, set: &ChangeSet) -> Result<Arc<DatabaseSchema>> {
+    let db = cached_schema(reg, conn_id).await?;
+    if find_table(&db.tables, set.schema.as_deref(), &set.table).is_some() {
+        return Ok(db);
+    }
+    refresh_schema(reg, conn_id).await
+}
+
+fn failed(sql: Vec<String>, error: ChangeError) -> ChangeSetResult {
+    ChangeSetResult { sql, applied: false, rows_affected: 0, error: Some(error) }
+}
+
+impl Planned {
+    fn build(&self, dialect: Dialect) -> Result<(String, sea_query_binder::SqlxValues)> {
+        match self {
+            Planned::Insert(s) => builder::build_insert(s, dialect),
+            Planned::Update(s) => builder::build_update(s, dialect),
+            Planned::Delete(s) => builder::build_delete(s, dialect),
+        }
+    }
+
+    fn preview(&self, dialect: Dialect) -> Result<String> {
+        match self {
+            Planned::Insert(s) => builder::preview_insert(s, dialect),
+            Planned::Update(s) => builder::preview_update(s, dialect),
+            Planned::Delete(s) => builder::preview_delete(s, dialect),
+        }
+    }
+}
+
+// Check every change against the table definition and turn it into a spec
+// with values coerced to the column types.
+fn plan(tables: &[TableDef], set: &ChangeSet) -> Result<Vec<Planned>, ChangeError> {
+    let err = |index, column: Option<&str>, message: String| ChangeError {
+        index,
+        column: column.map(str::to_string),
+        message,
+    };
+    let Some(table) = find_table(tables, set.schema.as_deref(), &set.table) else {
+        return Err(err(0, None, format!("table {} not found", set.table)));
+    };
+    let pk: Vec<&ColumnDef> = table.columns.iter().filter(|c| c.is_pk).collect();
+
+    let mut planned = Vec::with_capacity(set.changes.len());
+    for (index, change) in set.changes.iter().enumerate() {
+        let values = |pairs: &[(String, JsonValue)]| -> Result<Vec<(String, JsonValue)>, ChangeError> {
+            pairs
+                .iter()
+                .map(|(name, v)| {
+                    let col = column(table, name).map_err(|m| err(index, Some(name), m))?;
+                    let v = coerce(v, col).map_err(|m| err(index, Some(name), m))?;
+                    Ok((name.clone(), v))
+                })
+                .collect()
+        };
+        let key = |pairs: &[(String, JsonValue)]| -> Result<Vec<FilterCond>, ChangeError> {
+            if pk.is_empty() {
+                return Err(err(index, None, format!("table {} has no primary key", table.name)));
+            }
+            let pairs = values(pairs)?;
+            for k in &pk {
+                if !pairs.iter().any(|(n, v)| n == &k.name && !v.is_null()) {
+                    return Err(err(index, Some(&k.name), "missing primary key value".into()));
+                }
+            }
+            Ok(pairs
+                .into_iter()
+                .map(|(column, value)| FilterCond { column, op: "=".into(), value })
+                .collect())
+        };
+
+        planned.push(match change {
+            RowChange::Insert { values: v } => {
+                let v = values(v)?;
+                let missing = table.columns.iter().find(|c| {
+                    !c.nullable && c.default.is_none() && !c.is_pk && !v.iter().any(|(n, _)| n == &c.name)
+                });
+                if let Some(c) = missing {
+                    return Err(err(index, Some(&c.name), "column is NOT NULL and has no default".into()));
+                }
+                Planned::Insert(InsertSpec { schema: set.schema.clone(), table: table.name.clone(), values: v })
+            }
+            RowChange::Update { key: k, values: v } => {
+                if v.is_empty() {
+                    return Err(err(index, None, "no values to update".into()));
+                }
+                Planned::Update(UpdateSpec {
+                    schema: set.schema.clone(),
+                    table: table.name.clone(),
+                    values: values(v)?,
+                    filters: key(k)?,
+                })
+            }
+            RowChange::Delete { key: k } => {
+                Planned::Delete(DeleteSpec { schema: set.schema.clone(), table: table.name.clone(), filters: key(k)? })
+            }
+        });
+    }
+    Ok(planned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::validate::tests::col;
+    use crate::db::{execute_sql, TempDb};
+
+    #[test]
+    fn plan_reports_failing_change_and_column() {
+        let table = TableDef {
+            schema: "main".into(),
+            name: "users".into(),
+            type_: "table".into(),
+            columns: vec![
+                col("id", "INTEGER", false, true),
+                col("name", "TEXT", false, false),
+                col("age", "INTEGER", true, false),
+            ],
+            foreign_keys: vec![],
+            indexes: vec![],
+            unique_constraints: vec![],
+            checks: vec![],
+        };
+        let set = |changes| ChangeSet { schema: None, table: "users".into(), changes };
+        let pair = |c: &str, v: JsonValue| (c.to_string(), v);
+
+        let ok = plan(
+            std::slice::from_ref(&table),
+            &set(vec![
+                RowChange::Insert { values: vec![pair("name", "a".into())] },
+                RowChange::Update { key: vec![pair("id", "1".into())], values: vec![pair("age", "3".into())] },
+                RowChange::Delete { key: vec![pair("id", 2.into())] },
+            ]),
+        );
+        assert_eq!(ok.map(|p| p.len()), Ok(3));
+
+        let bad = plan(
+            std::slice::from_ref(&table),
+            &set(vec![
+                RowChange::Delete { key: vec![pair("id", 2.into())] },
+                RowChange::Update { key: vec![pair("id", 1.into())], values: vec![pair("age", "old".into())] },
+            ]),
+        );
+        let e = bad.err().unwrap();
+        assert_eq!((e.index, e.column.as_deref()), (1, Some("age")));
+
+        let missing = plan(std::slice::from_ref(&table), &set(vec![RowChange::Insert { values: vec![] }]));
+        assert_eq!(missing.err().unwrap().column.as_deref(), Some("name"));
+
+        let no_key = plan(std::slice::from_ref(&table), &set(vec![RowChange::Delete { key: vec![] }]));
+        assert_eq!(no_key.err().unwrap().column.as_deref(), Some("id"));
+    }
+
+    /// A change set is applied atomically: one failing change rolls back the
+    /// others and reports its index.
+    #[tokio::test]
+    async fn change_set_applies_atomically_sqlite() -> anyhow::Result<()> {
+        let db = TempDb::open("cs").await?;
+        execute_sql(&db.reg, &db.conn_id, "CREATE TABLE t (id INTEGER PRIMARY KEY, n INTEGER NOT NULL)", 10, None, None).await?;
+        execute_sql(&db.reg, &db.conn_id, "INSERT INTO t VALUES (1, 10), (2, 20)", 10, None, None).await?;
+        let pair = |c: &str, v: serde_json::Value| (c.to_string(), v);
+        async fn total(reg: &Registry, conn_id: &str) -> anyhow::Result<serde_json::Value> {
+            let r = execute_sql(reg, conn_id, "SELECT sum(n) FROM t", 10, None, None).await?;
+            Ok(r.rows[0][0].clone())
+        }
+
+        let failing = ChangeSet {
+            schema: None,
+            table: "t".into(),
+            changes: vec![
+                RowChange::Update { key: vec![pair("id", 1.into())], values: vec![pair("n", "11".into())] },
+                RowChange::Delete { key: vec![pair("id", 99.into())] },
+            ],
+        };
+        let preview = preview(&db.reg, &db.conn_id, &failing).await?;
+        assert_eq!(preview.sql.len(), 2);
+        assert!(preview.sql[0].contains("11"));
+        let res = apply(&db.reg, &db.conn_id, &failing).await?;
+        assert!(!res.applied);
+        assert_eq!(res.error.map(|e| e.index), Some(1));
+        assert_eq!(total(&db.reg, &db.conn_id).await?, serde_json::json!(30));
+
+        let ok = ChangeSet {
+            schema: None,
+            table: "t".into(),
+            changes: vec![
+                RowChange::Update { key: vec![pair("id", 1.into())], values: vec![pair("n", "11".into())] },
+                RowChange::Insert { values: vec![pair("n", 5.into())] },
+                RowChange::Delete { key: vec![pair("id", 2.into())] },
+            ],
+        };
+        let res = apply(&db.reg, &db.conn_id, &ok).await?;
+        assert!(res.applied);
+        assert_eq!(res.rows_affected, 3);
+        assert_eq!(total(&db.reg, &db.conn_id).await?, serde_json::json!(16));
+        Ok(())
+    }
+}
@@ -0,0 +1,146 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::builder::{self, SelectSpec};
+use super::cancel::{self, QueryTag};
+use super::{execute_sql_with_binds, session, DynPool, QueryResult, Registry};
+
+/// How to count the rows behind a browsed page.
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum CountMode {
+    /// `COUNT(*)` with the spec's joins, filters and grouping.
+    Exact,
+    /// The table size from planner statistics; ignores filters.
+    Estimate,
+}
+
+/// Total rows for a builder query.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct RowCount {
+    pub rows: i64,
+    pub exact: bool,
+}
+
+/// Count the rows `spec` selects. An estimate is None when the database has
+/// no statistics for the table yet (e.g. SQLite before `ANALYZE`). An exact
+/// count runs as a companion of `query_id`, so cancelling the page stops it.
+pub async fn total(
+    reg: &Registry,
+    pool: &DynPool,
+    conn_id: &str,
+    session_id: Option<&str>,
+    spec: &SelectSpec,
+    mode: CountMode,
+    query_id: Option<&str>,
+) -> Result<Option<RowCount>> {
+    match mode {
+        CountMode::Exact => {
+            let (sql, values) = builder::build_count(spec, pool.dialect())?;
+            let query_id = query_id.map(|q| cancel::companion_id(q, "count"));
+            // inside a transaction the count sees its uncommitted rows
+            let r = match session_id {
+                Some(session_id) => {
+                    session::run(reg, conn_id, session_id, &sql, Some(values), 1, None, query_id.as_deref()).await?
+                }
+                None => {
+                    let tag = query_id.as_deref().map(|query_id| QueryTag { running: &reg.running, conn_id, query_id });
+                    execute_sql_with_binds(pool, &sql, values, 1, tag.as_ref()).await?
+                }
+            };
+            Ok(Some(RowCount { rows: first_int(&r)?, exact: true }))
+        }
+        CountMode::Estimate => {
+            let rows = estimate(pool, spec.schema.as_deref(), &spec.table).await?;
+            Ok(rows.map(|rows| RowCount { rows, exact: false }))
+        }
+    }
+}
+
+fn first_int(r: &QueryResult) -> Result<i64> {
+    let cell = r.rows.first().and_then(|row| row.first());
+    // MySQL and Postgres may hand back big integers as text
+    match cell {
+        Some(serde_json::Value::Number(n)) => n.as_i64(),
+        Some(serde_json::Value::String(s)) => s.parse().ok(),
+        _ => None,
+    }
+    .ok_or_else(|| anyhow::anyhow!("count query returned no integer"))
+}
+
+/// Row estimate from `pg_class.reltuples`, `information_schema.tables.table_rows`
+/// or `sqlite_stat1`.
+pub async fn estimate(pool: &DynPool, schema: Option<&str>, table: &str) -> Result<Option<i64>> {
+    let rows = match pool {
+        DynPool::Sqlite(p) => {
+            // sqlite_stat1 only exists once ANALYZE has run. The first number
+            // of `stat` is the row count of the table (idx NULL) or an index.
+            let stat1 = match schema {
+                Some(s) => format!("\"{}\".sqlite_stat1", s.replace('"', "\"\"")),
+                None => "sqlite_stat1".to_string(),
+            };
+            let stat: Option<String> = match sqlx::query_scalar(&format!(
+                "SELECT stat FROM {} WHERE tbl = ? ORDER BY idx IS NOT NULL LIMIT 1",
+                stat1
+            ))
+            .bind(table)
+            .fetch_optional(p)
+            .await
+            {
+                Ok(stat) => stat,
+                Err(sqlx::Error::Database(e)) if e.message().contains("no such table") => None,
+                Err(e) => return Err(e.into()),
+            };
+            stat.and_then(|s| s.split(' ').next().and_then(|n| n.parse().ok()))
+        }
+        DynPool::Postgres(p) => {
+            // reltuples is -1 until the table is first vacuumed or analyzed
+            let n: Option<f32> = sqlx::query_scalar(
+                "SELECT reltuples FROM pg_class WHERE oid = to_regclass(coalesce(quote_ident($2) || '.', '') || quote_ident($1))",
+            )
+            .bind(table)
+            .bind(schema)
+            .fetch_optional(p)
+            .await?;
+            n.filter(|n| *n >= 0.0).map(|n| n as i64)
+        }
+        DynPool::MySql(p) => {
+            let n: Option<Option<u64>> = sqlx::query_scalar(
+                "SELECT TABLE_ROWS FROM information_schema.TABLES WHERE TABLE_SCHEMA = COALESCE(?, DATABASE()) AND TABLE_NAME = ?",
+            )
+            .bind(schema)
+            .bind(table)
+            .fetch_optional(p)
+            .await?;
+            n.flatten().map(|n| n as i64)
+        }
+    };
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{execute_sql, pool_of, TempDb};
+
+    /// Exact counts follow the filters; estimates need ANALYZE on SQLite.
+    #[tokio::test]
+    async fn counts_rows_exactly_and_by_estimate_sqlite() -> anyhow::Result<()> {
+        let db = TempDb::open("count").await?;
+        execute_sql(&db.reg, &db.conn_id, "CREATE TABLE t (id INTEGER PRIMARY KEY, grp INTEGER NOT NULL)", 10, None, None).await?;
+        execute_sql(&db.reg, &db.conn_id, "INSERT INTO t VALUES (1, 2), (2, 1), (3, 2), (4, 1), (5, 2)", 10, None, None).await?;
+        let pool = pool_of(&db.reg, &db.conn_id).await?;
+        let spec: SelectSpec = serde_json::from_value(serde_json::json!({
+            "table": "t", "columns": [], "limit": 1,
+            "filters": [{ "column": "grp", "op": "=", "value": 2 }]
+        }))?;
+
+        let exact = total(&db.reg, &pool, &db.conn_id, None, &spec, CountMode::Exact, None).await?;
+        assert_eq!(exact, Some(RowCount { rows: 3, exact: true }));
+        assert_eq!(total(&db.reg, &pool, &db.conn_id, None, &spec, CountMode::Estimate, None).await?, None);
+        execute_sql(&db.reg, &db.conn_id, "ANALYZE", 10, None, None).await?;
+        let estimate = total(&db.reg, &pool, &db.conn_id, None, &spec, CountMode::Estimate, None).await?;
+        assert_eq!(estimate, Some(RowCount { rows: 5, exact: false }));
+        Ok(())
+    }
+}
@@ -0,0 +1,301 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine as _;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, SecondsFormat, Utc};
+use serde_json::Value;
+use sqlx::mysql::MySqlRow;
+use sqlx::postgres::types::{Oid, PgInterval, PgMoney};
+use sqlx::postgres::{PgRow, PgTypeInfo, PgTypeKind};
+use sqlx::sqlite::SqliteRow;
+use sqlx::types::ipnetwork::IpNetwork;
+use sqlx::types::mac_address::MacAddress;
+use sqlx::types::{BigDecimal, Uuid};
+use sqlx::{Column, Row, TypeInfo, ValueRef};
+
+// Cells are decoded from the column's type info rather than by trial and error.
+// Timestamps are rendered as ISO-8601, decimals as exact strings (JSON numbers
+// would lose precision), binary data as base64, and arrays as JSON arrays.
+// Postgres sends values in binary, so types without a dedicated decoder are
+// read as text only where that format is the text itself (enum labels, domains
+// over text); anything else, such as ranges or geometric types, becomes null.
+// MySQL values without a decoder fall back to their text form, else null.
+
+/// Decode every cell of a SQLite row into JSON.
+pub fn sqlite_row_values(row: &SqliteRow) -> Vec<Value> {
+    (0..row.len())
+        .map(|i| sqlite_value(row, i).unwrap_or(Value::Null))
+        .collect()
+}
+
+/// Decode every cell of a Postgres row into JSON.
+pub fn pg_row_values(row: &PgRow) -> Vec<Value> {
+    (0..row.len())
+        .map(|i| pg_value(row, i).unwrap_or(Value::Null))
+        .collect()
+}
+
+/// Decode every cell of a MySQL row into JSON.
+pub fn mysql_row_values(row: &MySqlRow) -> Vec<Value> {
+    (0..row.len())
+        .map(|i| mysql_value(row, i).unwrap_or_else(|_| text_fallback(row, i)))
+        .collect()
+}
+
+fn text_fallback<R>(row: &R, i: usize) -> Value
+where
+    R: Row,
+    usize: sqlx::ColumnIndex<R>,
+    for<'r> String: sqlx::Decode<'r, R::Database>,
+{
+    row.try_get_unchecked::<String, _>(i)
+        .map(Value::from)
+        .unwrap_or(Value::Null)
+}
+
+fn sqlite_value(row: &SqliteRow, i: usize) -> Result<Value, sqlx::Error> {
+    // SQLite is dynamically typed: the storage class of the value decides how
+    // it is read, the declared column type only refines integers into booleans.
+    let raw = row.try_get_raw(i)?;
+    if raw.is_null() {
+        return Ok(Value::Null);
+    }
+    let storage = raw.type_info().name().to_string();
+    let declared = row.column(i).type_info().name().to_ascii_uppercase();
+
+    Ok(match storage.as_str() {
+        "INTEGER" | "BOOLEAN" if declared == "BOOLEAN" => Value::from(row.try_get::<bool, _>(i)?),
+        "INTEGER" | "BOOLEAN" => Value::from(row.try_get::<i64, _>(i)?),
+        "REAL" => Value::from(row.try_get::<f64, _>(i)?),
+        "BLOB" => Value::from(STANDARD.encode(row.try_get::<Vec<u8>, _>(i)?)),
+        _ => Value::from(row.try_get::<String, _>(i)?),
+    })
+}
+
+fn pg_value(row: &PgRow, i: usize) -> Result<Value, sqlx::Error> {
+    if row.try_get_raw(i)?.is_null() {
+        return Ok(Value::Null);
+    }
+    let ty = row.column(i).type_info().name().to_string();
+
+    Ok(match ty.as_str() {
+        "BOOL" => Value::from(row.try_get::<bool, _>(i)?),
+        "INT2" => Value::from(row.try_get::<i16, _>(i)?),
+        "INT4" => Value::from(row.try_get::<i32, _>(i)?),
+        "INT8" => Value::from(row.try_get::<i64, _>(i)?),
+        "OID" => Value::from(row.try_get::<Oid, _>(i)?.0),
+        "FLOAT4" => f32_json(row.try_get::<f32, _>(i)?),
+        "FLOAT8" => Value::from(row.try_get::<f64, _>(i)?),
+        "NUMERIC" => Value::from(row.try_get::<BigDecimal, _>(i)?.to_string()),
+        "MONEY" => Value::from(row.try_get::<PgMoney, _>(i)?.to_bigdecimal(2).to_string()),
+        "TEXT" | "VARCHAR" | "CHAR" | "NAME" => Value::from(row.try_get::<String, _>(i)?),
+        "\"CHAR\"" => Value::from((row.try_get::<i8, _>(i)? as u8 as char).to_string()),
+        "UUID" => Value::from(row.try_get::<Uuid, _>(i)?.to_string()),
+        "JSON" | "JSONB" => row.try_get::<Value, _>(i)?,
+        "TIMESTAMP" => Value::from(iso_datetime(row.try_get::<NaiveDateTime, _>(i)?)),
+        "TIMESTAMPTZ" => Value::from(iso_datetime_utc(row.try_get::<DateTime<Utc>, _>(i)?)),
+        "DATE" => Value::from(iso_date(row.try_get::<NaiveDate, _>(i)?)),
+        "TIME" => Value::from(iso_time(row.try_get::<NaiveTime, _>(i)?)),
+        "INTERVAL" => Value::from(iso_interval(&row.try_get::<PgInterval, _>(i)?)),
+        "INET" | "CIDR" => Value::from(row.try_get::<IpNetwork, _>(i)?.to_string()),
+        "MACADDR" => Value::from(row.try_get::<MacAddress, _>(i)?.to_string()),
+        "BYTEA" => Value::from(STANDARD.encode(row.try_get::<Vec<u8>, _>(i)?)),
+
+        "BOOL[]" => array(row.try_get::<Vec<Option<bool>>, _>(i)?, Value::from),
+        "INT2[]" => array(row.try_get::<Vec<Option<i16>>, _>(i)?, Value::from),
+        "INT4[]" => array(row.try_get::<Vec<Option<i32>>, _>(i)?, Value::from),
+        "INT8[]" => array(row.try_get::<Vec<Option<i64>>, _>(i)?, Value::from),
+        "FLOAT4[]" => array(row.try_get::<Vec<Option<f32>>, _>(i)?, f32_json),
+        "FLOAT8[]" => array(row.try_get::<Vec<Option<f64>>, _>(i)?, Value::from),
+        "NUMERIC[]" => array(row.try_get::<Vec<Option<BigDecimal>>, _>(i)?, |v| {
+            Value::from(v.to_string())
+        }),
+        "TEXT[]" | "VARCHAR[]" | "CHAR[]" | "NAME[]" => {
+            array(row.try_get::<Vec<Option<String>>, _>(i)?, Value::from)
+        }
+        "UUID[]" => array(row.try_get::<Vec<Option<Uuid>>, _>(i)?, |v| Value::from(v.to_string())),
+        "JSON[]" | "JSONB[]" => array(row.try_get::<Vec<Option<Value>>, _>(i)?, |v| v),
+        "TIMESTAMP[]" => array(row.try_get::<Vec<Option<NaiveDateTime>>, _>(i)?, |v| {
+            Value::from(iso_datetime(v))
+        }),
+        "TIMESTAMPTZ[]" => array(row.try_get::<Vec<Option<DateTime<Utc>>>, _>(i)?, |v| {
+            Value::from(iso_datetime_utc(v))
+        }),
+        "DATE[]" => array(row.try_get::<Vec<Option<NaiveDate>>, _>(i)?, |v| Value::from(iso_date(v))),
+        "TIME[]" => array(row.try_get::<Vec<Option<NaiveTime>>, _>(i)?, |v| Value::from(iso_time(v))),
+        "INET[]" | "CIDR[]" => array(row.try_get::<Vec<Option<IpNetwork>>, _>(i)?, |v| {
+            Value::from(v.to_string())
+        }),
+        "BYTEA[]" => array(row.try_get::<Vec<Option<Vec<u8>>>, _>(i)?, |v| {
+            Value::from(STANDARD.encode(v))
+        }),
+
+        _ if text_format(row.column(i).type_info()) => Value::from(row.try_get_unchecked::<String, _>(i)?),
+        _ => Value::Null,
+    })
+}
+
+/// Whether the binary form of a Postgres type is its UTF-8 text.
+fn text_format(ty: &PgTypeInfo) -> bool {
+    match ty.kind() {
+        PgTypeKind::Enum(_) => true,
+        PgTypeKind::Domain(base) => text_format(base),
+        _ => matches!(
+            ty.name().to_ascii_uppercase().as_str(),
+            "TEXT" | "VARCHAR" | "CHAR" | "NAME" | "XML" | "CITEXT"
+        ),
+    }
+}
+
+fn mysql_value(row: &MySqlRow, i: usize) -> Result<Value, sqlx::Error> {
+    if row.try_get_raw(i)?.is_null() {
+        return Ok(Value::Null);
+    }
+    let ty = row.column(i).type_info().name().to_string();
+
+    Ok(match ty.as_str() {
+        "BOOLEAN" => Value::from(row.try_get::<bool, _>(i)?),
+        "TINYINT" | "SMALLINT" | "MEDIUMINT" | "INT" | "BIGINT" => {
+            Value::from(row.try_get::<i64, _>(i)?)
+        }
+        t if t.ends_with(" UNSIGNED") => Value::from(row.try_get::<u64, _>(i)?),
+        "YEAR" | "BIT" => Value::from(row.try_get::<u64, _>(i)?),
+        "FLOAT" => f32_json(row.try_get::<f32, _>(i)?),
+        "DOUBLE" => Value::from(row.try_get::<f64, _>(i)?),
+        "DECIMAL" => Value::from(row.try_get::<BigDecimal, _>(i)?.to_string()),
+        "DATE" => Value::from(iso_date(row.try_get::<NaiveDate, _>(i)?)),
+        "TIME" => Value::from(iso_time(row.try_get::<NaiveTime, _>(i)?)),
+        "DATETIME" => Value::from(iso_datetime(row.try_get::<NaiveDateTime, _>(i)?)),
+        "TIMESTAMP" => Value::from(iso_datetime_utc(row.try_get::<DateTime<Utc>, _>(i)?)),
+        "JSON" => row.try_get::<Value, _>(i)?,
+        "BINARY" | "VARBINARY" | "TINYBLOB" | "BLOB" | "MEDIUMBLOB" | "LONGBLOB" | "GEOMETRY" => {
+            Value::from(STANDARD.encode(row.try_get::<Vec<u8>, _>(i)?))
+        }
+        _ => Value::from(row.try_get::<String, _>(i)?),
+    })
+}
+
+// ---------- formatting ----------
+
+/// JSON number with the digits the f32 was stored with; widening it to f64
+/// directly would turn 0.1 into 0.10000000149011612.
+pub fn f32_json(v: f32) -> Value {
+    Value::from(v.to_string().parse::<f64>().unwrap_or(f64::from(v)))
+}
+
+fn array<T>(items: Vec<Option<T>>, f: impl Fn(T) -> Value) -> Value {
+    Value::Array(items.into_iter().map(|v| v.map(&f).unwrap_or(Value::Null)).collect())
+}
+
+fn iso_datetime(v: NaiveDateTime) -> String {
+    v.format("%Y-%m-%dT%H:%M:%S%.f").to_string()
+}
+
+fn iso_datetime_utc(v: DateTime<Utc>) -> String {
+    v.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+}
+
+fn iso_date(v: NaiveDate) -> String {
+    v.format("%Y-%m-%d").to_string()
+}
+
+fn iso_time(v: NaiveTime) -> String {
+    v.format("%H:%M:%S%.f").to_string()
+}
+
+/// ISO-8601 duration, e.g. `P1Y2M3DT4H5M6.5S`; Postgres keeps months, days and
+/// microseconds separately so they are not normalised into each other.
+fn iso_interval(v: &PgInterval) -> String {
+    let mut out = String::from("P");
+    let (years, months) = (v.months / 12, v.months % 12);
+    if years != 0 {
+        out.push_str(&format!("{}Y", years));
+    }
+    if months != 0 {
+        out.push_str(&format!("{}M", months));
+    }
+    if v.days != 0 {
+        out.push_str(&format!("{}D", v.days));
+    }
+
+    let micros = v.microseconds;
+    if micros != 0 {
+        let sign = if micros < 0 { "-" } else { "" };
+        let abs = micros.unsigned_abs();
+        let (hours, rem) = (abs / 3_600_000_000, abs % 3_600_000_000);
+        let (mins, rem) = (rem / 60_000_000, rem % 60_000_000);
+        let (secs, frac) = (rem / 1_000_000, rem % 1_000_000);
+        out.push('T');
+        if hours != 0 {
+            out.push_str(&format!("{}{}H", sign, hours));
+        }
+        if mins != 0 {
+            out.push_str(&format!("{}{}M", sign, mins));
+        }
+        if secs != 0 || frac != 0 {
+            let frac = if frac == 0 {
+                String::new()
+            } else {
+                format!(".{:06}", frac).trim_end_matches('0').to_string()
+            };
+            out.push_str(&format!("{}{}{}S", sign, secs, frac));
+        }
+    }
+
+    if out == "P" {
+        out.push_str("T0S");
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn f32_keeps_its_shortest_digits() {
+        assert_eq!(f32_json(0.1), serde_json::json!(0.1));
+        assert_eq!(f32_json(-3.25e-7), serde_json::json!(-3.25e-7));
+        assert_eq!(f32_json(f32::NAN), Value::Null);
+    }
+
+    #[test]
+    fn iso_interval_renders_each_component() {
+        let iv = PgInterval { months: 14, days: 3, microseconds: 3_723_500_000 };
+        assert_eq!(iso_interval(&iv), "P1Y2M3DT1H2M3.5S");
+        let zero = PgInterval { months: 0, days: 0, microseconds: 0 };
+        assert_eq!(iso_interval(&zero), "PT0S");
+        let neg = PgInterval { months: 0, days: 0, microseconds: -90_000_000 };
+        assert_eq!(iso_interval(&neg), "PT-1M-30S");
+    }
+
+    /// SQLite values are decoded from their storage class, with declared
+    /// BOOLEAN columns rendered as JSON booleans.
+    #[tokio::test]
+    async fn sqlite_values_follow_storage_class() -> anyhow::Result<()> {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await?;
+        sqlx::query("CREATE TABLE t (i INTEGER, r REAL, s TEXT, b BLOB, f BOOLEAN, n TEXT);")
+            .execute(&pool)
+            .await?;
+        sqlx::query("INSERT INTO t VALUES (42, 1.5, '2024-01-02T03:04:05', x'0102', 1, NULL);")
+            .execute(&pool)
+            .await?;
+
+        let row = sqlx::query("SELECT * FROM t").fetch_one(&pool).await?;
+        let values = sqlite_row_values(&row);
+        assert_eq!(
+            values,
+            vec![
+                serde_json::json!(42),
+                serde_json::json!(1.5),
+                serde_json::json!("2024-01-02T03:04:05"),
+                serde_json::json!("AQI="),
+                serde_json::json!(true),
+                Value::Null,
+            ]
+        );
+        Ok(())
+    }
+}
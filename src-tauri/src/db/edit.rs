@@ -0,0 +1,221 @@
+use anyhow::Result;
+use sea_query_binder::SqlxValues;
+
+use super::builder::{self, BulkInsertSpec, DeleteSpec, FilterCond, InsertSpec, UpdateSpec};
+use super::validate;
+use super::{check_spec, pool_of, schema, session, DynPool, QueryResult, Registry};
+
+/// Insert one row. With a `session_id` it runs inside that transaction.
+pub async fn insert(
+    reg: &Registry,
+    conn_id: &str,
+    session_id: Option<&str>,
+    spec: &InsertSpec,
+) -> Result<QueryResult> {
+    let spec = &mut spec.clone();
+    validate::ensure(check_spec(reg, conn_id, spec).await?)?;
+    let pool = pool_of(reg, conn_id).await?;
+    let (sql, values) = builder::build_insert(spec, pool.dialect())?;
+    run(reg, &pool, conn_id, session_id, &sql, values).await
+}
+
+/// Insert many rows, chunked under the bind limit, in one transaction: the
+/// session's when given, else a new one. `rows_affected` sums the chunks;
+/// MySQL counts an upserted row that changed as 2.
+pub async fn bulk_insert(
+    reg: &Registry,
+    conn_id: &str,
+    session_id: Option<&str>,
+    spec: &BulkInsertSpec,
+) -> Result<QueryResult> {
+    let spec = &mut spec.clone();
+    validate::ensure(check_spec(reg, conn_id, spec).await?)?;
+    let pool = pool_of(reg, conn_id).await?;
+    let dialect = pool.dialect();
+    let chunks = builder::build_bulk_insert(spec, dialect)?;
+
+    let mut total: Option<QueryResult> = None;
+    let mut add = |r: QueryResult| match &mut total {
+        Some(t) => {
+            t.rows_affected = Some(t.rows_affected.unwrap_or(0) + r.rows_affected.unwrap_or(0));
+            t.last_insert_id = r.last_insert_id;
+            t.elapsed_ms += r.elapsed_ms;
+        }
+        None => total = Some(r),
+    };
+    match session_id {
+        Some(session_id) => {
+            for (sql, values) in chunks {
+                add(session::run(reg, conn_id, session_id, &sql, Some(values), 0, None, None).await?);
+            }
+        }
+        None => {
+            let mut conn = pool.acquire().await?;
+            conn.begin().await?;
+            for (sql, values) in chunks {
+                match conn.run(&sql, Some(values), 0, None).await {
+                    Ok(r) => add(r),
+                    Err(e) => {
+                        conn.execute("ROLLBACK").await?;
+                        return Err(e);
+                    }
+                }
+            }
+            conn.execute("COMMIT").await?;
+        }
+    }
+    total.ok_or_else(|| anyhow::anyhow!("bulk insert has no rows"))
+}
+
+/// Update the rows matching `spec.filters`. Unless `allow_unpinned` is set,
+/// the filters must pin one row by primary key (see [`builder::pins_primary_key`]).
+pub async fn update(
+    reg: &Registry,
+    conn_id: &str,
+    session_id: Option<&str>,
+    spec: &UpdateSpec,
+    allow_unpinned: bool,
+) -> Result<QueryResult> {
+    let spec = &mut spec.clone();
+    validate::ensure(check_spec(reg, conn_id, spec).await?)?;
+    let pool = pool_of(reg, conn_id).await?;
+    if !allow_unpinned {
+        ensure_pinned(&pool, spec.schema.as_deref(), &spec.table, &spec.filters).await?;
+    }
+    let (sql, values) = builder::build_update(spec, pool.dialect())?;
+    run(reg, &pool, conn_id, session_id, &sql, values).await
+}
+
+/// Delete the rows matching `spec.filters`, guarded like [`update`].
+pub async fn delete(
+    reg: &Registry,
+    conn_id: &str,
+    session_id: Option<&str>,
+    spec: &DeleteSpec,
+    allow_unpinned: bool,
+) -> Result<QueryResult> {
+    let spec = &mut spec.clone();
+    validate::ensure(check_spec(reg, conn_id, spec).await?)?;
+    let pool = pool_of(reg, conn_id).await?;
+    if !allow_unpinned {
+        ensure_pinned(&pool, spec.schema.as_deref(), &spec.table, &spec.filters).await?;
+    }
+    let (sql, values) = builder::build_delete(spec, pool.dialect())?;
+    run(reg, &pool, conn_id, session_id, &sql, values).await
+}
+
+async fn ensure_pinned(pool: &DynPool, schema: Option<&str>, table: &str, filters: &[FilterCond]) -> Result<()> {
+    let pk = schema::primary_key(pool, schema, table).await?;
+    if pk.is_empty() {
+        anyhow::bail!("table {} has no primary key; refusing to modify rows without allow_unpinned", table);
+    }
+    if !builder::pins_primary_key(filters, &pk) {
+        anyhow::bail!(
+            "filters must match one row by primary key ({}); refusing to modify rows without allow_unpinned",
+            pk.join(", ")
+        );
+    }
+    Ok(())
+}
+
+async fn run(
+    reg: &Registry,
+    pool: &DynPool,
+    conn_id: &str,
+    session_id: Option<&str>,
+    sql: &str,
+    values: SqlxValues,
+) -> Result<QueryResult> {
+    match session_id {
+        Some(session_id) => session::run(reg, conn_id, session_id, sql, Some(values), 0, None, None).await,
+        None => super::execute_sql_with_binds(pool, sql, values, 0, None).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{execute_sql, TempDb};
+
+    /// Grid edits go through bound parameters; updates and deletes must pin
+    /// a row by primary key unless explicitly allowed.
+    #[tokio::test]
+    async fn edit_specs_guard_by_primary_key_sqlite() -> anyhow::Result<()> {
+        let db = TempDb::open("edit").await?;
+        execute_sql(&db.reg, &db.conn_id, "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT)", 10, None, None).await?;
+        execute_sql(&db.reg, &db.conn_id, "CREATE TABLE log (msg TEXT)", 10, None, None).await?;
+
+        for name in ["alice", "bob"] {
+            let spec = InsertSpec { schema: None, table: "users".into(), values: vec![("name".into(), serde_json::json!(name))] };
+            let r = insert(&db.reg, &db.conn_id, None, &spec).await?;
+            assert_eq!(r.rows_affected, Some(1));
+        }
+
+        let eq = |c: &str, v: serde_json::Value| FilterCond { column: c.into(), op: "=".into(), value: v };
+        let rename = |filters| UpdateSpec {
+            schema: None,
+            table: "users".into(),
+            values: vec![("name".into(), serde_json::json!("carol"))],
+            filters,
+        };
+        let r = update(&db.reg, &db.conn_id, None, &rename(vec![eq("id", serde_json::json!(2))]), false).await?;
+        assert_eq!(r.rows_affected, Some(1));
+        let err = update(&db.reg, &db.conn_id, None, &rename(vec![eq("name", serde_json::json!("alice"))]), false).await;
+        assert!(err.err().unwrap().to_string().contains("primary key (id)"));
+
+        let keyless = DeleteSpec { schema: None, table: "log".into(), filters: vec![] };
+        assert!(delete(&db.reg, &db.conn_id, None, &keyless, false).await.is_err());
+        let all = DeleteSpec { schema: None, table: "users".into(), filters: vec![] };
+        assert!(delete(&db.reg, &db.conn_id, None, &all, false).await.is_err());
+        let r = delete(&db.reg, &db.conn_id, None, &all, true).await?;
+        assert_eq!(r.rows_affected, Some(2));
+        Ok(())
+    }
+
+    /// Bulk inserts upsert on the conflict key and roll back as a whole.
+    #[tokio::test]
+    async fn bulk_insert_upserts_sqlite() -> anyhow::Result<()> {
+        let db = TempDb::open("bulk").await?;
+        execute_sql(&db.reg, &db.conn_id, "CREATE TABLE t (id INTEGER PRIMARY KEY, name TEXT NOT NULL)", 10, None, None).await?;
+        let spec = |rows: serde_json::Value| -> anyhow::Result<BulkInsertSpec> {
+            Ok(serde_json::from_value(serde_json::json!({
+                "table": "t", "columns": ["id", "name"], "rows": rows, "upsert": { "conflict": ["id"] }
+            }))?)
+        };
+
+        let r = bulk_insert(&db.reg, &db.conn_id, None, &spec(serde_json::json!([[1, "a"], [2, "b"]]))?).await?;
+        assert_eq!(r.rows_affected, Some(2));
+        bulk_insert(&db.reg, &db.conn_id, None, &spec(serde_json::json!([[2, "B"], [3, "c"]]))?).await?;
+        let names = execute_sql(&db.reg, &db.conn_id, "SELECT name FROM t ORDER BY id", 10, None, None).await?;
+        assert_eq!(names.rows, [[serde_json::json!("a")], [serde_json::json!("B")], [serde_json::json!("c")]]);
+
+        let bad = spec(serde_json::json!([[4, "d"], [5, null]]))?;
+        let err = bulk_insert(&db.reg, &db.conn_id, None, &bad).await.err().unwrap();
+        assert!(err.to_string().contains("rows[1][1]: column is NOT NULL"), "{err}");
+        let mut no_upsert = spec(serde_json::json!([[4, "d"], [1, "dup"]]))?;
+        no_upsert.upsert = None;
+        assert!(bulk_insert(&db.reg, &db.conn_id, None, &no_upsert).await.is_err());
+        let count = execute_sql(&db.reg, &db.conn_id, "SELECT COUNT(*) FROM t", 10, None, None).await?;
+        assert_eq!(count.rows[0][0], serde_json::json!(3));
+        Ok(())
+    }
+
+    /// Chunks are sized for SQLite 3.32+, so the linked library must be one.
+    #[tokio::test]
+    async fn bulk_insert_fills_sqlite_bind_limit() -> anyhow::Result<()> {
+        let db = TempDb::open("binds").await?;
+        let version = execute_sql(&db.reg, &db.conn_id, "SELECT sqlite_version()", 10, None, None).await?;
+        let version: Vec<u32> = version.rows[0][0].as_str().unwrap().split('.').map(|p| p.parse().unwrap()).collect();
+        assert!(version[..] >= [3, 32][..], "SQLite {version:?} allows only 999 binds");
+
+        execute_sql(&db.reg, &db.conn_id, "CREATE TABLE t (id INTEGER PRIMARY KEY, name TEXT NOT NULL)", 10, None, None).await?;
+        let rows: Vec<_> = (0..16383).map(|i| serde_json::json!([i, "x"])).collect();
+        let spec: BulkInsertSpec = serde_json::from_value(serde_json::json!({
+            "table": "t", "columns": ["id", "name"], "rows": rows
+        }))?;
+        assert_eq!(builder::build_bulk_insert(&spec, crate::db::Dialect::Sqlite)?.len(), 1);
+        let r = bulk_insert(&db.reg, &db.conn_id, None, &spec).await?;
+        assert_eq!(r.rows_affected, Some(16383));
+        Ok(())
+    }
+}
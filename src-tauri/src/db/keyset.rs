@@ -0,0 +1,354 @@
+use anyhow::{bail, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine as _;
+use sea_query::{Alias, Condition, Expr, MysqlQueryBuilder, PostgresQueryBuilder, SimpleExpr, SqliteQueryBuilder};
+use sea_query_binder::{SqlxBinder, SqlxValues};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+use super::builder::{self, SelectSpec, Sort, SortKey};
+use super::schema::{ColumnDef, DatabaseSchema};
+use super::validate::find_table;
+use super::{Dialect, QueryResult};
+
+/// Cursors around a keyset page; None when there is no page that way.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct PageCursors {
+    pub next: Option<String>,
+    pub prev: Option<String>,
+}
+
+// What a cursor encodes: the key columns it was made for and their values in
+// the row at the page edge.
+#[derive(Serialize, Deserialize)]
+struct Cursor {
+    k: Vec<String>,
+    v: Vec<JsonValue>,
+}
+
+struct Key {
+    alias: String,
+    column: String,
+    /// Result column holding the key.
+    label: String,
+    asc: bool,
+    /// Postgres type to cast string cursor values to.
+    pg_type: String,
+}
+
+/// A keyset page of a [`SelectSpec`]: its sort keys with a unique tiebreaker
+/// appended, and the query rewritten to start after (or before) the cursor.
+pub struct Page {
+    /// The spec as run: key columns added to `columns`, sort on every key.
+    pub spec: SelectSpec,
+    keys: Vec<Key>,
+    cursor: Option<Vec<JsonValue>>,
+    backwards: bool,
+    limit: u64,
+}
+
+/// Plan a page for a spec with `keyset` set. Sort columns must be NOT NULL so
+/// the order is total; the primary key (or `unique_key`) breaks ties.
+pub fn plan(db: &DatabaseSchema, spec: &SelectSpec) -> Result<Page> {
+    let Some(keyset) = &spec.keyset else {
+        bail!("spec has no keyset");
+    };
+    if !spec.aggregates.is_empty() || !spec.group_by.is_empty() {
+        bail!("keyset pagination does not support aggregates or group_by");
+    }
+    let Some(limit) = spec.limit else {
+        bail!("keyset pagination needs a limit");
+    };
+    let Some(base) = find_table(&db.tables, spec.schema.as_deref(), &spec.table) else {
+        bail!("table {} not found", spec.table);
+    };
+
+    let column_def = |alias: &str, col: &str| -> Result<ColumnDef> {
+        let table = if alias == spec.table {
+            Some(base)
+        } else {
+            let j = spec.joins.iter().find(|j| j.alias() == alias);
+            j.and_then(|j| find_table(&db.tables, j.foreign_key.ref_schema.as_deref(), &j.foreign_key.to_table))
+        };
+        match table.and_then(|t| t.columns.iter().find(|c| c.name == col)) {
+            Some(c) => Ok(c.clone()),
+            None => bail!("column {}.{} not found", alias, col),
+        }
+    };
+
+    let mut keys: Vec<Key> = Vec::new();
+    let mut add = |name: &str, asc: bool, sorted: bool| -> Result<()> {
+        let (alias, col) = spec.resolve(name);
+        if keys.iter().any(|k| k.alias == alias && k.column == col) {
+            return Ok(());
+        }
+        let def = column_def(alias, col)?;
+        if sorted && def.nullable {
+            bail!("sort column {} is nullable; keyset pagination needs NOT NULL sort columns", name);
+        }
+        let label = if alias == spec.table { col.to_string() } else { format!("{}.{}", alias, col) };
+        keys.push(Key { alias: alias.into(), column: col.into(), label, asc, pg_type: def.data_type });
+        Ok(())
+    };
+    for k in spec.sort.iter().flat_map(Sort::keys) {
+        add(&k.column, k.asc, true)?;
+    }
+    let tiebreak: Vec<String> = if keyset.unique_key.is_empty() {
+        base.columns.iter().filter(|c| c.is_pk).map(|c| c.name.clone()).collect()
+    } else {
+        keyset.unique_key.clone()
+    };
+    if tiebreak.is_empty() {
+        bail!("table {} has no primary key; set unique_key for keyset pagination", spec.table);
+    }
+    for k in &tiebreak {
+        add(k, true, false)?;
+    }
+
+    let (cursor, backwards) = match (&keyset.after, &keyset.before) {
+        (Some(_), Some(_)) => bail!("set only one of after and before"),
+        (Some(c), None) => (Some(decode(c, &keys)?), false),
+        (None, Some(c)) => (Some(decode(c, &keys)?), true),
+        (None, None) => (None, false),
+    };
+
+    let mut run = spec.clone();
+    if !run.columns.is_empty() {
+        for k in &keys {
+            if !run.columns.iter().any(|c| spec.resolve(c) == (k.alias.as_str(), k.column.as_str())) {
+                run.columns.push(k.label.clone());
+            }
+        }
+    }
+    // backwards pages are read in reverse order and flipped afterwards
+    run.sort = Some(Sort::Keys(
+        keys.iter()
+            .map(|k| SortKey { column: k.label.clone(), asc: k.asc != backwards, nulls: None })
+            .collect(),
+    ));
+    // one extra row tells whether another page follows
+    run.limit = Some(limit + 1);
+    run.offset = None;
+    run.keyset = None;
+
+    Ok(Page { spec: run, keys, cursor, backwards, limit })
+}
+
+impl Page {
+    pub fn build(&self, dialect: Dialect) -> Result<(String, SqlxValues)> {
+        let mut stmt = builder::select_stmt(&self.spec, dialect)?;
+        if let Some(values) = &self.cursor {
+            stmt.cond_where(self.after(values, dialect));
+        }
+        Ok(match dialect {
+            Dialect::Postgres => stmt.build_sqlx(PostgresQueryBuilder),
+            Dialect::MySql    => stmt.build_sqlx(MysqlQueryBuilder),
+            Dialect::Sqlite   => stmt.build_sqlx(SqliteQueryBuilder),
+        })
+    }
+
+    // Rows past the cursor in read order:
+    // (k1 > v1) OR (k1 = v1 AND k2 > v2) OR ..., with < for descending keys.
+    fn after(&self, values: &[JsonValue], dialect: Dialect) -> Condition {
+        let mut any = Condition::any();
+        for i in 0..self.keys.len() {
+            let mut all = Condition::all();
+            for (k, v) in self.keys[..i].iter().zip(values) {
+                all = all.add(k.col().eq(k.value(v, dialect)));
+            }
+            let (k, v) = (&self.keys[i], &values[i]);
+            all = all.add(if k.asc != self.backwards {
+                k.col().gt(k.value(v, dialect))
+            } else {
+                k.col().lt(k.value(v, dialect))
+            });
+            any = any.add(all);
+        }
+        any
+    }
+
+    /// Trim the look-ahead row, restore order for backward pages and attach
+    /// the cursors.
+    pub fn finish(&self, mut r: QueryResult) -> Result<QueryResult> {
+        let idx = self
+            .keys
+            .iter()
+            .map(|k| r.columns.iter().position(|c| c == &k.label))
+            .collect::<Option<Vec<usize>>>()
+            .ok_or_else(|| anyhow::anyhow!("key columns missing from the result"))?;
+        let more = r.truncated || r.rows.len() as u64 > self.limit;
+        r.rows.truncate(self.limit as usize);
+        r.truncated = false;
+        if self.backwards {
+            r.rows.reverse();
+        }
+
+        let cursor = |row: &Vec<JsonValue>| {
+            let c = Cursor {
+                k: self.keys.iter().map(|k| k.label.clone()).collect(),
+                v: idx.iter().map(|&i| row[i].clone()).collect(),
+            };
+            STANDARD.encode(serde_json::to_vec(&c).unwrap_or_default())
+        };
+        // coming from a cursor means there are rows on its side
+        let (has_prev, has_next) = if self.backwards { (more, true) } else { (self.cursor.is_some(), more) };
+        r.page = Some(PageCursors {
+            next: r.rows.last().filter(|_| has_next).map(cursor),
+            prev: r.rows.first().filter(|_| has_prev).map(cursor),
+        });
+        Ok(r)
+    }
+}
+
+impl Key {
+    fn col(&self) -> Expr {
+        Expr::col((Alias::new(&self.alias), Alias::new(&self.column)))
+    }
+
+    // Cursor values come back as JSON, so timestamps, numerics and the like
+    // are strings; Postgres needs them cast to compare with the column.
+    fn value(&self, v: &JsonValue, dialect: Dialect) -> SimpleExpr {
+        match (dialect, v) {
+            (Dialect::Postgres, JsonValue::String(s)) => Expr::cust_with_values(
+                format!("CAST($1 AS \"{}\")", self.pg_type.replace('"', "\"\"")),
+                [s.as_str()],
+            ),
+            _ => builder::json_to_simple(v),
+        }
+    }
+}
+
+fn decode(cursor: &str, keys: &[Key]) -> Result<Vec<JsonValue>> {
+    let c: Cursor = STANDARD
+        .decode(cursor)
+        .ok()
+        .and_then(|b| serde_json::from_slice(&b).ok())
+        .ok_or_else(|| anyhow::anyhow!("invalid cursor"))?;
+    if c.k.len() != keys.len() || c.k.iter().zip(keys).any(|(a, k)| a != &k.label) || c.v.len() != keys.len() {
+        bail!("cursor was made for a different sort order");
+    }
+    Ok(c.v)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::schema::TableDef;
+    use crate::db::validate::tests::col;
+    use crate::db::{cached_schema, execute_sql, execute_sql_with_binds, pool_of, DynPool, TempDb};
+
+    #[test]
+    fn pages_by_sort_keys_then_primary_key() {
+        let db = DatabaseSchema {
+            dialect: "postgres".into(),
+            schemas: vec!["public".into()],
+            tables: vec![TableDef {
+                schema: "public".into(),
+                name: "events".into(),
+                type_: "table".into(),
+                columns: vec![col("id", "int4", false, true), col("at", "timestamp", false, false), col("note", "text", true, false)],
+                foreign_keys: vec![],
+                indexes: vec![],
+                unique_constraints: vec![],
+                checks: vec![],
+            }],
+        };
+        let spec = |keyset: serde_json::Value| -> SelectSpec {
+            serde_json::from_value(serde_json::json!({
+                "table": "events", "columns": ["note"], "filters": [], "limit": 2, "offset": null,
+                "sort": [{ "column": "at", "asc": false }], "keyset": keyset
+            }))
+            .unwrap()
+        };
+
+        let first = plan(&db, &spec(serde_json::json!({}))).unwrap();
+        assert_eq!(first.spec.columns, ["note", "at", "id"]);
+        let (sql, _) = first.build(Dialect::Postgres).unwrap();
+        assert_eq!(
+            sql,
+            r#"SELECT "events"."note", "events"."at", "events"."id" FROM "events" ORDER BY "events"."at" DESC, "events"."id" ASC LIMIT $1"#
+        );
+
+        let mut result = QueryResult {
+            columns: vec!["note".into(), "at".into(), "id".into()],
+            column_meta: vec![],
+            rows: vec![
+                vec!["a".into(), "2024-01-03 00:00:00".into(), 3.into()],
+                vec!["b".into(), "2024-01-02 00:00:00".into(), 2.into()],
+                vec!["c".into(), "2024-01-02 00:00:00".into(), 1.into()],
+            ],
+            truncated: false,
+            kind: crate::db::statement::StatementKind::Query,
+            rows_affected: None,
+            last_insert_id: None,
+            elapsed_ms: 0.0,
+            page: None,
+            total: None,
+        };
+        result = first.finish(result).unwrap();
+        assert_eq!(result.rows.len(), 2);
+        let cursors = result.page.unwrap();
+        assert_eq!(cursors.prev, None);
+        let next = cursors.next.unwrap();
+
+        let second = plan(&db, &spec(serde_json::json!({ "after": next }))).unwrap();
+        let (sql, values) = second.build(Dialect::Postgres).unwrap();
+        assert!(
+            sql.ends_with(
+                r#"WHERE "events"."at" < (CAST($1 AS "timestamp")) OR ("events"."at" = (CAST($2 AS "timestamp")) AND "events"."id" > $3) ORDER BY "events"."at" DESC, "events"."id" ASC LIMIT $4"#
+            ),
+            "{sql}"
+        );
+        assert_eq!(values.0 .0.len(), 4);
+
+        let back = plan(&db, &spec(serde_json::json!({ "before": next }))).unwrap();
+        let (sql, _) = back.build(Dialect::Sqlite).unwrap();
+        assert!(sql.ends_with(r#"ORDER BY "events"."at" ASC, "events"."id" DESC LIMIT ?"#), "{sql}");
+
+        let nullable = serde_json::json!({ "table": "events", "columns": [], "filters": [], "limit": 2, "offset": null,
+            "sort": [{ "column": "note" }], "keyset": {} });
+        assert!(plan(&db, &serde_json::from_value(nullable).unwrap()).is_err());
+        let other_order = serde_json::json!({ "table": "events", "columns": [], "filters": [], "limit": 2, "offset": null,
+            "sort": null, "keyset": { "after": next } });
+        let err = plan(&db, &serde_json::from_value(other_order).unwrap()).err().unwrap();
+        assert_eq!(err.to_string(), "cursor was made for a different sort order");
+    }
+
+    /// Keyset pages walk forward and back over rows with duplicate sort values.
+    #[tokio::test]
+    async fn keyset_pages_walk_both_ways_sqlite() -> anyhow::Result<()> {
+        let db = TempDb::open("keyset").await?;
+        execute_sql(&db.reg, &db.conn_id, "CREATE TABLE t (id INTEGER PRIMARY KEY, grp INTEGER NOT NULL)", 10, None, None).await?;
+        execute_sql(&db.reg, &db.conn_id, "INSERT INTO t VALUES (1, 2), (2, 1), (3, 2), (4, 1), (5, 2)", 10, None, None).await?;
+        let pool = pool_of(&db.reg, &db.conn_id).await?;
+        let schema = cached_schema(&db.reg, &db.conn_id).await?;
+
+        async fn page(
+            pool: &DynPool,
+            schema: &DatabaseSchema,
+            keyset: serde_json::Value,
+        ) -> anyhow::Result<(Vec<serde_json::Value>, PageCursors)> {
+            let spec: SelectSpec = serde_json::from_value(serde_json::json!({
+                "table": "t", "columns": ["id"], "filters": [], "limit": 2, "offset": null,
+                "sort": [{ "column": "grp", "asc": false }], "keyset": keyset
+            }))?;
+            let p = plan(schema, &spec)?;
+            let (sql, values) = p.build(Dialect::Sqlite)?;
+            let r = p.finish(execute_sql_with_binds(pool, &sql, values, 1000, None).await?)?;
+            Ok((r.rows.iter().map(|row| row[0].clone()).collect(), r.page.unwrap()))
+        }
+
+        let (ids, first) = page(&pool, &schema, serde_json::json!({})).await?;
+        assert_eq!(ids, [serde_json::json!(1), serde_json::json!(3)]);
+        assert!(first.prev.is_none());
+        let (ids, second) = page(&pool, &schema, serde_json::json!({ "after": first.next })).await?;
+        assert_eq!(ids, [serde_json::json!(5), serde_json::json!(2)]);
+        let (ids, last) = page(&pool, &schema, serde_json::json!({ "after": second.next })).await?;
+        assert_eq!(ids, [serde_json::json!(4)]);
+        assert!(last.next.is_none());
+        let (ids, back) = page(&pool, &schema, serde_json::json!({ "before": last.prev })).await?;
+        assert_eq!(ids, [serde_json::json!(5), serde_json::json!(2)]);
+        assert!(back.prev.is_some() && back.next.is_some());
+        Ok(())
+    }
+}
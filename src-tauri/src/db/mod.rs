@@ -1,18 +1,35 @@
 pub mod builder;
+pub mod cancel;
+pub mod changeset;
+pub mod count;
+pub mod decode;
+pub mod edit;
+pub mod keyset;
 pub mod schema;
+pub mod script;
+pub mod session;
+pub mod statement;
+pub mod validate;
 pub mod pool; 
 
-use std::{collections::HashMap, sync::Arc, time::{SystemTime, UNIX_EPOCH}};
-
-use base64::engine::general_purpose::STANDARD;
-use base64::Engine as _;
+use std::{collections::HashMap, str::FromStr, sync::Arc, time::{Instant, SystemTime, UNIX_EPOCH}};
 
 use anyhow::Result;
+use futures::{Stream, TryStreamExt};
+use sqlx::Either;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
-use sqlx::{self, Column, Pool, Row};
+use sea_query_binder::SqlxValues;
+use sqlx::pool::PoolConnection;
+use sqlx::{self, Column, Executor, Pool, Row, Statement, TypeInfo};
 use tokio::sync::RwLock;
 
+use cancel::{QueryTag, RunningQueries};
+use decode::{mysql_row_values, pg_row_values, sqlite_row_values};
+use schema::{CachedSchema, DatabaseSchema, SchemaCache};
+use session::Sessions;
+use statement::{statement_kind, StatementKind};
+
 /// DB driver kinds supported by the app.
 #[derive(Clone, Copy, Serialize, Deserialize, Debug)]
 #[serde(rename_all = "lowercase")]
@@ -46,12 +63,103 @@ impl DynPool {
             DynPool::MySql(_) => Dialect::MySql,
         }
     }
+
+    /// Most connections the pool will open at once.
+    pub fn max_connections(&self) -> u32 {
+        match self {
+            DynPool::Sqlite(p) => p.options().get_max_connections(),
+            DynPool::Postgres(p) => p.options().get_max_connections(),
+            DynPool::MySql(p) => p.options().get_max_connections(),
+        }
+    }
+
+    /// Check out a connection; statements run on it share one session.
+    pub async fn acquire(&self) -> Result<DynConn> {
+        Ok(match self {
+            DynPool::Sqlite(p) => DynConn::Sqlite(p.acquire().await?),
+            DynPool::Postgres(p) => DynConn::Postgres(p.acquire().await?),
+            DynPool::MySql(p) => DynConn::MySql(p.acquire().await?),
+        })
+    }
+}
+
+/// A connection checked out of a `DynPool`; returned to the pool on drop.
+pub enum DynConn {
+    Sqlite(PoolConnection<sqlx::Sqlite>),
+    Postgres(PoolConnection<sqlx::Postgres>),
+    MySql(PoolConnection<sqlx::MySql>),
+}
+
+impl DynConn {
+    /// Run one statement, with SeaQuery bind values if given, keeping at most
+    /// `cap` rows (or forwarding them to `sink`).
+    pub async fn run(
+        &mut self,
+        sql: &str,
+        values: Option<SqlxValues>,
+        cap: usize,
+        sink: Option<&mut BatchSink<'_>>,
+    ) -> Result<QueryResult> {
+        let kind = statement_kind(sql);
+        match self {
+            DynConn::Sqlite(c) => {
+                let described = describe_sqlite(c, sql).await;
+                let rows = match values {
+                    Some(v) => (&mut **c).fetch_many(sqlx::query_with(sql, v)),
+                    None => (&mut **c).fetch_many(sqlx::query(sql)),
+                };
+                collect_rows(rows.map_ok(sqlite_done), kind, cap, sqlite_row_values, described, sink).await
+            }
+            DynConn::Postgres(c) => {
+                let described = describe_pg(c, sql).await;
+                let rows = match values {
+                    Some(v) => (&mut **c).fetch_many(sqlx::query_with(sql, v)),
+                    None => (&mut **c).fetch_many(sqlx::query(sql)),
+                };
+                collect_rows(rows.map_ok(pg_done), kind, cap, pg_row_values, described, sink).await
+            }
+            DynConn::MySql(c) => {
+                let described = describe_mysql(c, sql).await;
+                let rows = match values {
+                    Some(v) => (&mut **c).fetch_many(sqlx::query_with(sql, v)),
+                    None => (&mut **c).fetch_many(sqlx::query(sql)),
+                };
+                collect_rows(rows.map_ok(mysql_done), kind, cap, mysql_row_values, described, sink).await
+            }
+        }
+    }
+
+    /// Execute a statement whose result is not needed (e.g. `BEGIN`, `COMMIT`).
+    pub async fn execute(&mut self, sql: &str) -> Result<()> {
+        match self {
+            DynConn::Sqlite(c) => c.execute(sql).await.map(|_| ())?,
+            DynConn::Postgres(c) => c.execute(sql).await.map(|_| ())?,
+            DynConn::MySql(c) => c.execute(sql).await.map(|_| ())?,
+        }
+        Ok(())
+    }
+
+    /// Start a transaction, spelled the way this connection's dialect wants.
+    pub async fn begin(&mut self) -> Result<()> {
+        self.execute(match self {
+            DynConn::MySql(_) => "START TRANSACTION",
+            _ => "BEGIN",
+        })
+        .await
+    }
 }
 
 /// Connection registry (shared state managed by Tauri).
 #[derive(Default)]
 pub struct Registry {
     pub inner: Arc<RwLock<HashMap<String, DynPool>>>,
+    /// Queries in flight across all connections, so they can be cancelled.
+    pub running: RunningQueries,
+    /// Open transactions, each pinned to its own connection.
+    pub sessions: Sessions,
+    /// Introspected schema per connection, served by `get_schema` and used
+    /// to validate builder specs.
+    pub schemas: SchemaCache,
 }
 
 impl Registry {
@@ -63,9 +171,73 @@ impl Registry {
 /// Result set returned to the frontend.
 #[derive(Serialize)]
 pub struct QueryResult {
-    pub columns: Vec<String>,
+    pub columns: Vec<String>,  // from the statement description, so also set for zero rows
+    pub column_meta: Vec<ColumnMeta>, // one entry per column, same order as `columns`
     pub rows: Vec<Vec<Value>>, // each row is a JSON array of cell values
-    pub truncated: bool,       // true if rows were cut off to respect the limit
+    pub truncated: bool,       // true if more rows exist beyond the limit
+    pub kind: StatementKind,
+    /// Rows inserted/updated/deleted; None for queries.
+    pub rows_affected: Option<u64>,
+    /// Id generated by an INSERT (SQLite rowid, MySQL AUTO_INCREMENT); Postgres
+    /// callers use `RETURNING`, whose rows come back in `rows`.
+    pub last_insert_id: Option<i64>,
+    /// Wall-clock execution time, excluding connection checkout.
+    pub elapsed_ms: f64,
+    /// Cursors for keyset pages of builder queries.
+    pub page: Option<keyset::PageCursors>,
+    /// Rows behind a builder query, when a count was requested.
+    pub total: Option<count::RowCount>,
+}
+
+impl QueryResult {
+    /// See [`builder::mark_sources`].
+    pub fn with_sources(mut self, spec: &builder::SelectSpec) -> Self {
+        builder::mark_sources(&mut self.column_meta, spec);
+        self
+    }
+}
+
+/// Mark every column as coming from `table` under its own name. Used for
+/// builder queries, where the selected columns are plain table columns.
+pub fn mark_source_table(meta: &mut [ColumnMeta], table: &str) {
+    for c in meta {
+        c.table = Some(table.to_string());
+        c.column = Some(c.name.clone());
+    }
+}
+
+/// Metadata for one result column.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct ColumnMeta {
+    pub name: String,
+    /// Type name as reported by the driver, e.g. `INT8`, `VARCHAR`, `TEXT`.
+    #[serde(rename = "type")]
+    pub type_name: String,
+    /// None when the driver cannot tell (e.g. computed expressions).
+    pub nullable: Option<bool>,
+    /// Source table and column, when resolvable.
+    pub table: Option<String>,
+    pub column: Option<String>,
+}
+
+/// Progress events pushed to the frontend while a streamed query runs.
+#[derive(Serialize, Clone, Debug)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum QueryEvent {
+    /// Sent first with the id to pass to `cancel_query`.
+    Started { query_id: String },
+    /// Column headers, sent once before the first batch of rows.
+    Columns { columns: Vec<String>, column_meta: Vec<ColumnMeta> },
+    /// A batch of rows in result order.
+    Rows { rows: Vec<Vec<Value>> },
+    /// Sent last; `truncated` is true if more rows exist beyond the limit.
+    Finished { row_count: usize, truncated: bool },
+}
+
+/// Receiver for streamed rows; `emit` is called with each event in order.
+pub struct BatchSink<'a> {
+    pub batch_size: usize,
+    pub emit: Box<dyn FnMut(QueryEvent) -> Result<()> + Send + 'a>,
 }
 
 /// Open a connection and register it. Returns a connection id.
@@ -73,9 +245,11 @@ pub async fn open_connection(reg: &Registry, driver: Driver, url: &str) -> Resul
     let id = gen_id();
     let pool = match driver {
         Driver::Sqlite => {
+            // REGEXP backs the builder's `regex` filter operator
+            let opts = sqlx::sqlite::SqliteConnectOptions::from_str(url)?.with_regexp();
             let pool = sqlx::sqlite::SqlitePoolOptions::new()
                 .max_connections(5)
-                .connect(url)
+                .connect_with(opts)
                 .await?;
             DynPool::Sqlite(pool)
         }
@@ -99,42 +273,215 @@ pub async fn open_connection(reg: &Registry, driver: Driver, url: &str) -> Resul
     Ok(id)
 }
 
-/// Close (forget) a connection by id.
+/// Close (forget) a connection by id. Queries still running on it are cancelled
+/// and its open transactions rolled back first. Closing is best-effort: the
+/// connection is forgotten even if a cancel or rollback fails, and those
+/// failures are returned afterwards.
 pub async fn close_connection(reg: &Registry, conn_id: &str) -> Result<()> {
+    let cancelled = cancel::cancel_all(&reg.running, conn_id).await;
+    let rolled_back = session::rollback_all(reg, conn_id).await;
     reg.inner.write().await.remove(conn_id);
-    Ok(())
+    reg.schemas.lock().unwrap().remove(conn_id);
+    cancelled.and(rolled_back)
 }
 
 /// Execute arbitrary SQL and return a JSON-friendly result.
-/// NOTE: For large datasets, prefer specifying a LIMIT/OFFSET on the SQL from the UI.
-pub async fn execute_sql(reg: &Registry, conn_id: &str, sql: &str, limit: u32) -> Result<QueryResult> {
-    let pools = reg.inner.read().await;
-    let pool = pools
+/// Rows are pulled lazily and fetching stops once `limit` rows are collected.
+/// When `query_id` is given the query can be aborted with [`cancel_query`].
+/// With a `session_id` it runs inside that open transaction instead.
+pub async fn execute_sql(
+    reg: &Registry,
+    conn_id: &str,
+    sql: &str,
+    limit: u32,
+    query_id: Option<&str>,
+    session_id: Option<&str>,
+) -> Result<QueryResult> {
+    if let Some(session_id) = session_id {
+        return session::run(reg, conn_id, session_id, sql, None, limit, None, query_id).await;
+    }
+    let pool = &pool_of(reg, conn_id).await?;
+
+    let tag = query_id.map(|query_id| QueryTag { running: &reg.running, conn_id, query_id });
+    let res = fetch_sql(pool, sql, limit, None, tag.as_ref()).await;
+    if let Ok(r) = &res {
+        schema_may_change(reg, conn_id, r.kind);
+    }
+    res
+}
+
+/// Like [`execute_sql`], but pushes rows to `sink` in batches as they arrive so
+/// the UI can render the first page while the rest is still being fetched.
+pub async fn stream_sql(
+    reg: &Registry,
+    conn_id: &str,
+    sql: &str,
+    limit: u32,
+    query_id: Option<&str>,
+    session_id: Option<&str>,
+    sink: &mut BatchSink<'_>,
+) -> Result<QueryResult> {
+    if let Some(session_id) = session_id {
+        return session::run(reg, conn_id, session_id, sql, None, limit, Some(sink), query_id).await;
+    }
+    let pool = &pool_of(reg, conn_id).await?;
+
+    let tag = query_id.map(|query_id| QueryTag { running: &reg.running, conn_id, query_id });
+    let res = fetch_sql(pool, sql, limit, Some(sink), tag.as_ref()).await;
+    if let Ok(r) = &res {
+        schema_may_change(reg, conn_id, r.kind);
+    }
+    res
+}
+
+/// Run a `;`-separated script statement by statement on one connection and
+/// return per-statement outcomes. See [`script::ScriptOptions`].
+pub async fn execute_script(
+    reg: &Registry,
+    conn_id: &str,
+    sql: &str,
+    opts: script::ScriptOptions,
+    limit: u32,
+    query_id: Option<&str>,
+) -> Result<script::ScriptResult> {
+    let pool = &pool_of(reg, conn_id).await?;
+
+    let tag = query_id.map(|query_id| QueryTag { running: &reg.running, conn_id, query_id });
+    let res = script::run_script(pool, sql, opts, limit, tag.as_ref()).await?;
+    for r in res.statements.iter().filter_map(|s| s.result.as_ref()) {
+        schema_may_change(reg, conn_id, r.kind);
+    }
+    Ok(res)
+}
+
+/// Abort a running query by id. Returns false if it already finished.
+pub async fn cancel_query(reg: &Registry, query_id: &str) -> Result<bool> {
+    cancel::cancel_query(&reg.running, query_id).await
+}
+
+/// The pool behind `conn_id`, cloned so the registry lock is not held while
+/// a query runs; `open_connection` would otherwise wait for it to finish.
+pub async fn pool_of(reg: &Registry, conn_id: &str) -> Result<DynPool> {
+    reg.inner
+        .read()
+        .await
         .get(conn_id)
-        .ok_or_else(|| anyhow::anyhow!("connection not found"))?;
+        .cloned()
+        .ok_or_else(|| anyhow::anyhow!("connection not found"))
+}
 
-    match pool {
-        DynPool::Sqlite(p) => {
-            let rows: Vec<sqlx::sqlite::SqliteRow> = sqlx::query(sql).fetch_all(p).await?;
-            Ok(rows_to_result_sqlite(rows, limit as usize))
-        }
-        DynPool::Postgres(p) => {
-            let rows: Vec<sqlx::postgres::PgRow> = sqlx::query(sql).fetch_all(p).await?;
-            Ok(rows_to_result_pg(rows, limit as usize))
-        }
-        DynPool::MySql(p) => {
-            let rows: Vec<sqlx::mysql::MySqlRow> = sqlx::query(sql).fetch_all(p).await?;
-            Ok(rows_to_result_mysql(rows, limit as usize))
-        }
+/// Schema of a connection, introspected on first use and reused until DDL
+/// runs through the app, [`schema_changed`] notices a change made elsewhere
+/// or [`refresh_schema`] is called.
+pub async fn cached_schema(reg: &Registry, conn_id: &str) -> Result<Arc<DatabaseSchema>> {
+    if let Some(c) = reg.schemas.lock().unwrap().get(conn_id) {
+        return Ok(c.schema.clone());
+    }
+    refresh_schema(reg, conn_id).await
+}
+
+/// Introspect a connection's schema again and cache it.
+pub async fn refresh_schema(reg: &Registry, conn_id: &str) -> Result<Arc<DatabaseSchema>> {
+    let pool = pool_of(reg, conn_id).await?;
+    // versioned first, so DDL racing the introspection shows up as a change
+    let version = schema::schema_version(&pool).await?;
+    let s = Arc::new(schema::inspect_schema(&pool).await?);
+    let cached = CachedSchema { schema: s.clone(), version };
+    reg.schemas.lock().unwrap().insert(conn_id.to_string(), cached);
+    Ok(s)
+}
+
+/// Whether the schema differs from the cached one, e.g. after a migration
+/// run by another client. Also true when nothing is cached, including after
+/// DDL through the app. A stale entry is dropped so specs are validated
+/// against a fresh introspection.
+pub async fn schema_changed(reg: &Registry, conn_id: &str) -> Result<bool> {
+    let pool = pool_of(reg, conn_id).await?;
+    let Some(cached) = reg.schemas.lock().unwrap().get(conn_id).map(|c| c.version.clone()) else {
+        return Ok(true);
+    };
+    if schema::schema_version(&pool).await? == cached {
+        return Ok(false);
     }
+    let mut schemas = reg.schemas.lock().unwrap();
+    // a refresh may have landed meanwhile
+    if schemas.get(conn_id).is_some_and(|c| c.version == cached) {
+        schemas.remove(conn_id);
+    }
+    Ok(true)
+}
+
+/// Tables and views of a connection without their details; not cached.
+pub async fn list_objects(reg: &Registry, conn_id: &str, filter: &schema::ObjectFilter) -> Result<schema::ObjectList> {
+    schema::list_objects(&pool_of(reg, conn_id).await?, filter).await
+}
+
+/// Introspect only the tables `filter` keeps. The partial result bypasses
+/// the schema cache, which always holds the whole database.
+pub async fn filtered_schema(reg: &Registry, conn_id: &str, filter: &schema::ObjectFilter) -> Result<DatabaseSchema> {
+    schema::inspect_filtered(&pool_of(reg, conn_id).await?, filter).await
+}
+
+/// Full definitions of the named tables, e.g. those visible in the ER view.
+pub async fn describe_tables(reg: &Registry, conn_id: &str, tables: &[schema::ObjectRef]) -> Result<Vec<schema::TableDef>> {
+    schema::describe_tables(&pool_of(reg, conn_id).await?, tables).await
+}
+
+/// Check a builder spec against the cached schema, coercing its values to the
+/// column types (see [`validate::Check`]). A table missing from the cache
+/// triggers one fresh introspection, as it may have been created since.
+pub async fn check_spec(reg: &Registry, conn_id: &str, spec: &mut impl validate::Check) -> Result<Vec<validate::SpecError>> {
+    let errors = spec.check(&*cached_schema(reg, conn_id).await?);
+    if errors.iter().any(|e| e.field == "table" || e.field.ends_with(".to_table")) {
+        return Ok(spec.check(&*refresh_schema(reg, conn_id).await?));
+    }
+    Ok(errors)
+}
+
+// Drop the cached schema after DDL so the next lookup introspects again.
+fn schema_may_change(reg: &Registry, conn_id: &str, kind: StatementKind) {
+    if kind == StatementKind::Ddl {
+        reg.schemas.lock().unwrap().remove(conn_id);
+    }
+}
+
+/// New id for a query handle returned to the frontend.
+pub fn new_query_id() -> String {
+    format!("q-{}", gen_id())
+}
+
+// Queries run on an explicitly acquired connection so a tagged query can be
+// matched to its server-side session when it has to be cancelled.
+async fn fetch_sql(
+    pool: &DynPool,
+    sql: &str,
+    limit: u32,
+    sink: Option<&mut BatchSink<'_>>,
+    tag: Option<&QueryTag<'_>>,
+) -> Result<QueryResult> {
+    fetch(pool, sql, None, limit, sink, tag).await
+}
+
+async fn fetch(
+    pool: &DynPool,
+    sql: &str,
+    values: Option<SqlxValues>,
+    limit: u32,
+    sink: Option<&mut BatchSink<'_>>,
+    tag: Option<&QueryTag<'_>>,
+) -> Result<QueryResult> {
+    let mut conn = pool.acquire().await?;
+    let tracked = cancel::track(tag, pool, &mut conn).await?;
+    let res = match cancel::ensure_running(tracked.as_ref()) {
+        Ok(()) => conn.run(sql, values, limit as usize, sink).await,
+        Err(e) => Err(e),
+    };
+    cancel::finish(tracked, &mut conn, res).await
 }
 
 /// Fetch database schema (schemas, tables, columns, minimal indexes/keys) as JSON.
 pub async fn get_schema(reg: &Registry, conn_id: &str) -> Result<Value> {
-    let pools = reg.inner.read().await;
-    let pool = pools
-        .get(conn_id)
-        .ok_or_else(|| anyhow::anyhow!("connection not found"))?;
+    let pool = &pool_of(reg, conn_id).await?;
 
     match pool {
         DynPool::Sqlite(p) => schema_sqlite(p).await,
@@ -154,6 +501,34 @@ fn gen_id() -> String {
     format!("{:x}-{}", nanos, std::process::id())
 }
 
+/// A SQLite file in the temp dir, opened in its own registry. The file is
+/// removed on drop, so a failing assertion does not leave it behind.
+#[cfg(test)]
+pub(crate) struct TempDb {
+    pub reg: Registry,
+    pub conn_id: String,
+    pub url: String,
+    path: std::path::PathBuf,
+}
+
+#[cfg(test)]
+impl TempDb {
+    pub async fn open(name: &str) -> Result<Self> {
+        let path = std::env::temp_dir().join(format!("inkless-{name}-{}.db", gen_id()));
+        let url = format!("sqlite://{}?mode=rwc", path.display());
+        let reg = Registry::new();
+        let conn_id = open_connection(&reg, Driver::Sqlite, &url).await?;
+        Ok(Self { reg, conn_id, url, path })
+    }
+}
+
+#[cfg(test)]
+impl Drop for TempDb {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
 // Helper to parse length/precision/scale from SQLite type strings.
 fn parse_len_prec_scale(ty: &str) -> (Option<u32>, Option<u32>, Option<u32>) {
     // Examples: VARCHAR(255), CHAR(32), NUMERIC(10,2), DECIMAL(12, 4)
@@ -171,155 +546,180 @@ fn parse_len_prec_scale(ty: &str) -> (Option<u32>, Option<u32>, Option<u32>) {
     (None, None, None)
 }
 
-fn rows_to_result_sqlite(rows: Vec<sqlx::sqlite::SqliteRow>, cap: usize) -> QueryResult {
-    let mut columns: Vec<String> = vec![];
+fn column_meta<C: Column>(cols: &[C], nullable: &[Option<bool>]) -> Vec<ColumnMeta> {
+    cols.iter()
+        .enumerate()
+        .map(|(i, c)| ColumnMeta {
+            name: c.name().to_string(),
+            type_name: c.type_info().name().to_string(),
+            nullable: nullable.get(i).copied().flatten(),
+            table: None,
+            column: None,
+        })
+        .collect()
+}
+
+/// Pull rows from a driver stream until `cap` rows are collected.
+///
+/// One extra row is requested past the cap so `truncated` reports whether more
+/// rows exist; the stream is then dropped and the driver stops fetching. When a
+/// sink is given, rows are forwarded in batches instead of being kept in the
+/// returned `QueryResult`. Column metadata comes from the statement description
+/// when available (so it is known even without rows), else from the first row.
+/// Statement completions in the stream carry the affected-row counts.
+async fn collect_rows<R, S>(
+    mut rows: S,
+    kind: StatementKind,
+    cap: usize,
+    decode: fn(&R) -> Vec<Value>,
+    described: Option<Vec<ColumnMeta>>,
+    mut sink: Option<&mut BatchSink<'_>>,
+) -> Result<QueryResult>
+where
+    R: Row,
+    S: Stream<Item = Result<Either<Done, R>, sqlx::Error>> + Unpin,
+{
+    let started = Instant::now();
+    let mut meta = described.unwrap_or_default();
     let mut out: Vec<Vec<Value>> = vec![];
+    let mut pending: Vec<Vec<Value>> = vec![];
+    let mut row_count = 0usize;
+    let mut truncated = false;
+    let mut rows_affected = 0u64;
+    let mut last_insert_id = None;
+
+    if !meta.is_empty() {
+        if let Some(s) = sink.as_deref_mut() {
+            (s.emit)(QueryEvent::Columns { columns: names_of(&meta), column_meta: meta.clone() })?;
+        }
+    }
 
-    for (idx, row) in rows.into_iter().enumerate() {
-        if idx == 0 {
-            columns = row
-                .columns()
-                .iter()
-                .map(|c| c.name().to_string())
-                .collect();
+    while let Some(step) = rows.try_next().await? {
+        let row = match step {
+            Either::Left(done) => {
+                rows_affected += done.rows_affected;
+                if done.rows_affected > 0 {
+                    last_insert_id = done.last_insert_id.or(last_insert_id);
+                }
+                continue;
+            }
+            Either::Right(row) => row,
+        };
+        if row_count == 0 && meta.is_empty() {
+            meta = column_meta(row.columns(), &[]);
+            if let Some(s) = sink.as_deref_mut() {
+                (s.emit)(QueryEvent::Columns { columns: names_of(&meta), column_meta: meta.clone() })?;
+            }
         }
-        if out.len() >= cap {
+        if row_count >= cap {
+            truncated = true;
+            // keep draining RETURNING rows so the completion (and count) is seen
+            if kind.modifies_rows() {
+                continue;
+            }
             break;
         }
-        let mut rec = Vec::with_capacity(row.len());
-        for i in 0..row.len() {
-            // Try common types; fallback to string or null
-            if let Ok(v) = row.try_get::<i64, _>(i) {
-                rec.push(Value::from(v));
-            } else if let Ok(v) = row.try_get::<f64, _>(i) {
-                rec.push(Value::from(v));
-            } else if let Ok(v) = row.try_get::<bool, _>(i) {
-                rec.push(Value::from(v));
-            } else if let Ok(v) = row.try_get::<String, _>(i) {
-                rec.push(Value::from(v));
-            } else if let Ok(v) = row.try_get::<Vec<u8>, _>(i) {
-                rec.push(Value::from(STANDARD.encode(v)));
-            } else {
-                rec.push(Value::Null);
+        row_count += 1;
+        match sink.as_deref_mut() {
+            Some(s) => {
+                pending.push(decode(&row));
+                if pending.len() >= s.batch_size {
+                    (s.emit)(QueryEvent::Rows { rows: std::mem::take(&mut pending) })?;
+                }
             }
+            None => out.push(decode(&row)),
+        }
+    }
+    drop(rows);
+
+    if let Some(s) = sink {
+        if !pending.is_empty() {
+            (s.emit)(QueryEvent::Rows { rows: pending })?;
         }
-        out.push(rec);
+        (s.emit)(QueryEvent::Finished { row_count, truncated })?;
     }
 
-    let truncated = out.len() >= cap;
-    QueryResult {
-        columns,
+    Ok(QueryResult {
+        columns: names_of(&meta),
+        column_meta: meta,
         rows: out,
         truncated,
-    }
+        kind,
+        rows_affected: kind.modifies_rows().then_some(rows_affected),
+        last_insert_id: if kind == StatementKind::Insert { last_insert_id } else { None },
+        elapsed_ms: started.elapsed().as_secs_f64() * 1000.0,
+        page: None,
+        total: None,
+    })
 }
 
-fn rows_to_result_pg(rows: Vec<sqlx::postgres::PgRow>, cap: usize) -> QueryResult {
-    let mut columns: Vec<String> = vec![];
-    let mut out: Vec<Vec<Value>> = vec![];
+/// Completion of one statement, normalised across drivers.
+struct Done {
+    rows_affected: u64,
+    last_insert_id: Option<i64>,
+}
 
-    for (idx, row) in rows.into_iter().enumerate() {
-        if idx == 0 {
-            columns = row
-                .columns()
-                .iter()
-                .map(|c| c.name().to_string())
-                .collect();
-        }
-        if out.len() >= cap {
-            break;
-        }
-        let mut rec = Vec::with_capacity(row.len());
-        for i in 0..row.len() {
-            if let Ok(v) = row.try_get::<i64, _>(i) {
-                rec.push(Value::from(v));
-            } else if let Ok(v) = row.try_get::<f64, _>(i) {
-                rec.push(Value::from(v));
-            } else if let Ok(v) = row.try_get::<bool, _>(i) {
-                rec.push(Value::from(v));
-            } else if let Ok(v) = row.try_get::<String, _>(i) {
-                rec.push(Value::from(v));
-            } else if let Ok(v) = row.try_get::<Vec<u8>, _>(i) {
-                rec.push(Value::from(STANDARD.encode(v)));
-            } else {
-                rec.push(Value::Null);
-            }
-        }
-        out.push(rec);
-    }
+fn sqlite_done<R>(step: Either<sqlx::sqlite::SqliteQueryResult, R>) -> Either<Done, R> {
+    step.map_left(|r| Done { rows_affected: r.rows_affected(), last_insert_id: Some(r.last_insert_rowid()) })
+}
 
-    let truncated = out.len() >= cap;
-    QueryResult {
-        columns,
-        rows: out,
-        truncated,
-    }
+fn pg_done<R>(step: Either<sqlx::postgres::PgQueryResult, R>) -> Either<Done, R> {
+    step.map_left(|r| Done { rows_affected: r.rows_affected(), last_insert_id: None })
 }
 
-fn rows_to_result_mysql(rows: Vec<sqlx::mysql::MySqlRow>, cap: usize) -> QueryResult {
-    let mut columns: Vec<String> = vec![];
-    let mut out: Vec<Vec<Value>> = vec![];
+fn mysql_done<R>(step: Either<sqlx::mysql::MySqlQueryResult, R>) -> Either<Done, R> {
+    step.map_left(|r| Done {
+        rows_affected: r.rows_affected(),
+        // 0 means the statement generated no id
+        last_insert_id: Some(r.last_insert_id() as i64).filter(|id| *id != 0),
+    })
+}
 
-    for (idx, row) in rows.into_iter().enumerate() {
-        if idx == 0 {
-            columns = row
-                .columns()
-                .iter()
-                .map(|c| c.name().to_string())
-                .collect();
-        }
-        if out.len() >= cap {
-            break;
-        }
-        let mut rec = Vec::with_capacity(row.len());
-        for i in 0..row.len() {
-            if let Ok(v) = row.try_get::<i64, _>(i) {
-                rec.push(Value::from(v));
-            } else if let Ok(v) = row.try_get::<f64, _>(i) {
-                rec.push(Value::from(v));
-            } else if let Ok(v) = row.try_get::<bool, _>(i) {
-                rec.push(Value::from(v));
-            } else if let Ok(v) = row.try_get::<String, _>(i) {
-                rec.push(Value::from(v));
-            } else if let Ok(v) = row.try_get::<Vec<u8>, _>(i) {
-                rec.push(Value::from(STANDARD.encode(v)));
-            } else {
-                rec.push(Value::Null);
-            }
-        }
-        out.push(rec);
-    }
+// Statement description is best-effort: if it fails, the query itself reports
+// the real error and metadata falls back to the first row.
+async fn describe_pg(conn: &mut sqlx::PgConnection, sql: &str) -> Option<Vec<ColumnMeta>> {
+    let d = conn.describe(sql).await.ok()?;
+    Some(column_meta(d.columns(), &d.nullable))
+}
 
-    let truncated = out.len() >= cap;
-    QueryResult {
-        columns,
-        rows: out,
-        truncated,
-    }
+async fn describe_mysql(conn: &mut sqlx::MySqlConnection, sql: &str) -> Option<Vec<ColumnMeta>> {
+    let d = conn.describe(sql).await.ok()?;
+    Some(column_meta(d.columns(), &d.nullable))
+}
+
+// sqlx's SQLite `describe` infers nullability by walking the query plan, which
+// can take minutes on recursive CTEs; preparing is cheap and still yields the
+// column names and declared types.
+async fn describe_sqlite(conn: &mut sqlx::SqliteConnection, sql: &str) -> Option<Vec<ColumnMeta>> {
+    let stmt = conn.prepare(sql).await.ok()?;
+    Some(column_meta(stmt.columns(), &[]))
+}
+
+fn names_of(meta: &[ColumnMeta]) -> Vec<String> {
+    meta.iter().map(|c| c.name.clone()).collect()
 }
 
 /// Execute SQL with bound parameters generated by SeaQuery.
 pub async fn execute_sql_with_binds(
     pool: &DynPool,
     sql: &str,
-    values: sea_query_binder::SqlxValues,
+    values: SqlxValues,
     limit: u32,
+    tag: Option<&QueryTag<'_>>,
 ) -> anyhow::Result<QueryResult> {
-    let cap = limit as usize;
-    match pool {
-        DynPool::Sqlite(p) => {
-            let rows: Vec<sqlx::sqlite::SqliteRow> = sqlx::query_with(sql, values).fetch_all(p).await?;
-            Ok(rows_to_result_sqlite(rows, cap))
-        }
-        DynPool::Postgres(p) => {
-            let rows: Vec<sqlx::postgres::PgRow> = sqlx::query_with(sql, values).fetch_all(p).await?;
-            Ok(rows_to_result_pg(rows, cap))
-        }
-        DynPool::MySql(p) => {
-            let rows: Vec<sqlx::mysql::MySqlRow> = sqlx::query_with(sql, values).fetch_all(p).await?;
-            Ok(rows_to_result_mysql(rows, cap))
-        }
-    }
+    fetch(pool, sql, Some(values), limit, None, tag).await
+}
+
+/// Streaming variant of [`execute_sql_with_binds`]; rows are pushed to `sink`.
+pub async fn stream_sql_with_binds(
+    pool: &DynPool,
+    sql: &str,
+    values: SqlxValues,
+    limit: u32,
+    tag: Option<&QueryTag<'_>>,
+    sink: &mut BatchSink<'_>,
+) -> anyhow::Result<QueryResult> {
+    fetch(pool, sql, Some(values), limit, Some(sink), tag).await
 }
 
 // -------- schema discovery per dialect --------
@@ -649,19 +1049,25 @@ mod it_sqlite {
 
         // Build a SELECT spec: only active users whose name starts with 'a', ordered by id
         let spec = SelectSpec {
+            schema: None,
             table: "users".into(),
             columns: vec!["id".into(), "name".into()],
             filters: vec![
-                FilterCond { column: "active".into(), op: "=".into(), value: serde_json::json!(1) },
-                FilterCond { column: "name".into(), op: "like".into(), value: serde_json::json!("a%") },
+                FilterCond { column: "active".into(), op: "=".into(), value: serde_json::json!(1) }.into(),
+                FilterCond { column: "name".into(), op: "like".into(), value: serde_json::json!("a%") }.into(),
             ],
-            sort: Some(("id".into(), true)),
+            sort: Some(builder::Sort::Single("id".into(), true)),
             limit: Some(100),
             offset: Some(0),
+            joins: vec![],
+            aggregates: vec![],
+            group_by: vec![],
+            having: vec![],
+            keyset: None,
         };
 
-        let (sql, values) = build_select(&spec, Dialect::Sqlite);
-        let result = execute_sql_with_binds(&dyn_pool, &sql, values, 1000).await?;
+        let (sql, values) = build_select(&spec, Dialect::Sqlite).unwrap();
+        let result = execute_sql_with_binds(&dyn_pool, &sql, values, 1000, None).await?;
 
         // Expect two rows: alice(id=1) and adam(id=3), in ascending id order
         assert_eq!(result.columns, vec!["id".to_string(), "name".to_string()]);
@@ -671,6 +1077,240 @@ mod it_sqlite {
         assert_eq!(result.rows[1][0], serde_json::json!(3));
         assert_eq!(result.rows[1][1], serde_json::json!("adam"));
         assert!(!result.truncated);
+
+        // Column metadata comes from the prepared statement's declared types
+        let meta = result.with_sources(&spec).column_meta;
+        assert_eq!(meta.len(), 2);
+        assert_eq!(meta[0].name, "id");
+        assert_eq!(meta[0].type_name, "INTEGER");
+        assert_eq!(meta[1].type_name, "TEXT");
+        assert_eq!(meta[1].table.as_deref(), Some("users"));
+        assert_eq!(meta[1].column.as_deref(), Some("name"));
+        Ok(())
+    }
+
+    /// `truncated` is only set when rows exist beyond the limit, and streamed
+    /// rows arrive in batches after the column headers.
+    #[tokio::test]
+    async fn stream_rows_in_batches_and_stop_at_cap_sqlite() -> anyhow::Result<()> {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await?;
+        sqlx::query("CREATE TABLE n (v INTEGER);").execute(&pool).await?;
+        sqlx::query("INSERT INTO n (v) VALUES (1), (2), (3), (4), (5);")
+            .execute(&pool)
+            .await?;
+        let dyn_pool = DynPool::Sqlite(pool.clone());
+
+        let exact = fetch_sql(&dyn_pool, "SELECT v FROM n ORDER BY v", 5, None, None).await?;
+        assert_eq!(exact.rows.len(), 5);
+        assert!(!exact.truncated);
+
+        let mut events = Vec::new();
+        let mut sink = BatchSink {
+            batch_size: 2,
+            emit: Box::new(|ev| {
+                events.push(ev);
+                Ok(())
+            }),
+        };
+        let streamed = fetch_sql(&dyn_pool, "SELECT v FROM n ORDER BY v", 3, Some(&mut sink), None).await?;
+        drop(sink);
+
+        assert!(streamed.truncated);
+        assert!(streamed.rows.is_empty());
+        assert_eq!(streamed.columns, vec!["v".to_string()]);
+        assert_eq!(events.len(), 4);
+        assert!(matches!(&events[0], QueryEvent::Columns { columns, .. } if columns == &["v"]));
+        assert!(matches!(&events[1], QueryEvent::Rows { rows } if rows.len() == 2));
+        assert!(matches!(&events[2], QueryEvent::Rows { rows } if rows.len() == 1 && rows[0][0] == serde_json::json!(3)));
+        assert!(matches!(&events[3], QueryEvent::Finished { row_count: 3, truncated: true }));
+        Ok(())
+    }
+
+    /// A long-running SQLite query is interrupted by `cancel_query` and
+    /// reports a cancellation error instead of running to completion.
+    #[tokio::test]
+    async fn cancel_running_query_sqlite() -> anyhow::Result<()> {
+        let reg = Arc::new(Registry::new());
+        let conn_id = open_connection(&reg, Driver::Sqlite, "sqlite::memory:").await?;
+
+        let task = {
+            let reg = reg.clone();
+            let conn_id = conn_id.clone();
+            tokio::spawn(async move {
+                let sql = "WITH RECURSIVE c(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM c WHERE x < 1000000000) \
+                           SELECT count(*) FROM c";
+                execute_sql(&reg, &conn_id, sql, 10, Some("q-test"), None).await
+            })
+        };
+
+        while !reg.running.lock().unwrap().contains_key("q-test") {
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        }
+        // the running query does not hold the registry lock
+        let other = open_connection(&reg, Driver::Sqlite, "sqlite::memory:");
+        tokio::time::timeout(std::time::Duration::from_secs(5), other).await??;
+        assert!(cancel_query(&reg, "q-test").await?);
+
+        let err = task.await?.err().expect("query should have been cancelled");
+        assert_eq!(err.to_string(), "query cancelled");
+        assert!(reg.running.lock().unwrap().is_empty());
+        assert!(!cancel_query(&reg, "q-test").await?);
         Ok(())
     }
-}
\ No newline at end of file
+
+    /// Zero-row results keep their headers (taken from the prepared statement)
+    /// on the plain, bound and streamed paths.
+    #[tokio::test]
+    async fn empty_results_keep_column_headers_sqlite() -> anyhow::Result<()> {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await?;
+        sqlx::query("CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT, active INTEGER);")
+            .execute(&pool)
+            .await?;
+        let dyn_pool = DynPool::Sqlite(pool.clone());
+        let expected = vec!["id".to_string(), "name".to_string(), "active".to_string()];
+
+        let plain = fetch_sql(&dyn_pool, "SELECT * FROM users", 100, None, None).await?;
+        assert_eq!(plain.columns, expected);
+        assert_eq!(plain.column_meta.len(), 3);
+        assert!(plain.rows.is_empty());
+        assert!(!plain.truncated);
+
+        let spec = SelectSpec {
+            schema: None,
+            table: "users".into(),
+            columns: vec!["id".into(), "name".into()],
+            filters: vec![FilterCond { column: "active".into(), op: "=".into(), value: serde_json::json!(1) }.into()],
+            sort: None,
+            limit: None,
+            offset: None,
+            joins: vec![],
+            aggregates: vec![],
+            group_by: vec![],
+            having: vec![],
+            keyset: None,
+        };
+        let (sql, values) = build_select(&spec, Dialect::Sqlite).unwrap();
+        let bound = execute_sql_with_binds(&dyn_pool, &sql, values, 100, None).await?;
+        assert_eq!(bound.columns, vec!["id".to_string(), "name".to_string()]);
+
+        let mut events = Vec::new();
+        let mut sink = BatchSink {
+            batch_size: 10,
+            emit: Box::new(|ev| {
+                events.push(ev);
+                Ok(())
+            }),
+        };
+        fetch_sql(&dyn_pool, "SELECT * FROM users", 100, Some(&mut sink), None).await?;
+        drop(sink);
+        assert_eq!(events.len(), 2);
+        assert!(matches!(&events[0], QueryEvent::Columns { columns, .. } if columns == &expected));
+        assert!(matches!(&events[1], QueryEvent::Finished { row_count: 0, truncated: false }));
+        Ok(())
+    }
+
+    /// Non-SELECT statements report their kind, affected rows and generated
+    /// ids; `RETURNING` rows come back alongside the count.
+    #[tokio::test]
+    async fn dml_reports_rows_affected_and_last_insert_id_sqlite() -> anyhow::Result<()> {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await?;
+        let dyn_pool = DynPool::Sqlite(pool.clone());
+
+        let ddl = fetch_sql(&dyn_pool, "CREATE TABLE t (id INTEGER PRIMARY KEY, v TEXT)", 10, None, None).await?;
+        assert_eq!(ddl.kind, StatementKind::Ddl);
+        assert_eq!(ddl.rows_affected, Some(0));
+
+        let ins = fetch_sql(&dyn_pool, "INSERT INTO t (v) VALUES ('a'), ('b')", 10, None, None).await?;
+        assert_eq!(ins.kind, StatementKind::Insert);
+        assert_eq!(ins.rows_affected, Some(2));
+        assert_eq!(ins.last_insert_id, Some(2));
+        assert!(ins.rows.is_empty());
+
+        let upd = fetch_sql(&dyn_pool, "UPDATE t SET v = 'z' WHERE id = 1 RETURNING id, v", 10, None, None).await?;
+        assert_eq!(upd.kind, StatementKind::Update);
+        assert_eq!(upd.rows_affected, Some(1));
+        assert_eq!(upd.last_insert_id, None);
+        assert_eq!(upd.rows, vec![vec![serde_json::json!(1), serde_json::json!("z")]]);
+
+        let sel = fetch_sql(&dyn_pool, "SELECT * FROM t", 10, None, None).await?;
+        assert_eq!(sel.kind, StatementKind::Query);
+        assert_eq!(sel.rows_affected, None);
+        assert!(sel.elapsed_ms >= 0.0);
+        Ok(())
+    }
+
+    /// Scripts run statement by statement; a failure inside a transaction
+    /// rolls back everything, outside one later statements still run.
+    #[tokio::test]
+    async fn script_runs_statements_and_rolls_back_on_error_sqlite() -> anyhow::Result<()> {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await?;
+        let dyn_pool = DynPool::Sqlite(pool.clone());
+        let opts = |transaction, stop_on_error| script::ScriptOptions { transaction, stop_on_error };
+
+        let sql = "CREATE TABLE t (id INTEGER PRIMARY KEY, v TEXT NOT NULL);\n\
+                   INSERT INTO t (v) VALUES ('a;b');\n\
+                   INSERT INTO t (v) VALUES (NULL);\n\
+                   SELECT v FROM t;";
+        let tx = script::run_script(&dyn_pool, sql, opts(true, false), 100, None).await?;
+        assert_eq!(tx.total, 4);
+        assert_eq!(tx.statements.len(), 3);
+        assert_eq!(tx.committed, Some(false));
+        assert_eq!(tx.statements[1].result.as_ref().unwrap().rows_affected, Some(1));
+        assert_eq!(tx.statements[2].index, 2);
+        assert!(tx.statements[2].error.as_deref().unwrap().contains("NOT NULL"));
+        let exists = fetch_sql(&dyn_pool, "SELECT name FROM sqlite_master WHERE name = 't'", 10, None, None).await?;
+        assert!(exists.rows.is_empty());
+
+        let plain = script::run_script(&dyn_pool, sql, opts(false, false), 100, None).await?;
+        assert_eq!(plain.statements.len(), 4);
+        assert_eq!(plain.committed, None);
+        assert!(plain.statements[2].error.is_some());
+        let last = plain.statements[3].result.as_ref().unwrap();
+        assert_eq!(last.kind, StatementKind::Query);
+        assert_eq!(last.rows, vec![vec![serde_json::json!("a;b")]]);
+
+        // a query id that is already running is refused before BEGIN
+        let running = RunningQueries::default();
+        let tag = QueryTag { running: &running, conn_id: "c", query_id: "q" };
+        let _held = cancel::track(Some(&tag), &dyn_pool, &mut dyn_pool.acquire().await?).await?;
+        let err = script::run_script(&dyn_pool, "SELECT 1", opts(true, false), 100, Some(&tag)).await;
+        assert_eq!(err.err().unwrap().to_string(), "query id already in use");
+        dyn_pool.acquire().await?.begin().await?;
+        Ok(())
+    }
+
+    /// DDL from another client is noticed by the version check; writes are not.
+    #[tokio::test]
+    async fn schema_changed_detects_outside_ddl_sqlite() -> anyhow::Result<()> {
+        let db = TempDb::open("version").await?;
+        execute_sql(&db.reg, &db.conn_id, "CREATE TABLE t (id INTEGER PRIMARY KEY)", 10, None, None).await?;
+        assert!(schema_changed(&db.reg, &db.conn_id).await?, "nothing cached yet");
+
+        let first = cached_schema(&db.reg, &db.conn_id).await?;
+        assert!(!schema_changed(&db.reg, &db.conn_id).await?);
+        execute_sql(&db.reg, &db.conn_id, "INSERT INTO t (id) VALUES (1)", 10, None, None).await?;
+        assert!(!schema_changed(&db.reg, &db.conn_id).await?);
+        assert!(Arc::ptr_eq(&first, &cached_schema(&db.reg, &db.conn_id).await?));
+
+        let other = sqlx::SqlitePool::connect(&db.url).await?;
+        sqlx::query("CREATE TABLE migrated (id INTEGER PRIMARY KEY)").execute(&other).await?;
+        other.close().await;
+        assert!(schema_changed(&db.reg, &db.conn_id).await?);
+        let fresh = cached_schema(&db.reg, &db.conn_id).await?;
+        assert!(fresh.tables.iter().any(|t| t.name == "migrated"));
+        assert!(!schema_changed(&db.reg, &db.conn_id).await?);
+        Ok(())
+    }
+}
@@ -1,3 +1,6 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -40,16 +43,54 @@ pub struct ColumnDef {
     pub scale: Option<u32>,
 }
 
-/// A foreign key constraint between tables.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A foreign key constraint between tables: `columns[i]` references
+/// `ref_columns[i]` of `to_table`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct ForeignKeyDef {
-    pub from: String,
+    /// Constraint name; SQLite does not report one.
+    #[serde(default)]
+    pub name: Option<String>,
+    pub columns: Vec<String>,
     pub ref_schema: Option<String>,
     pub to_table: String,
-    pub to: String,
+    pub ref_columns: Vec<String>,
+    /// Referential actions, e.g. `CASCADE` or `NO ACTION`.
+    #[serde(default)]
+    pub on_update: Option<String>,
+    #[serde(default)]
+    pub on_delete: Option<String>,
+}
+
+/// An index on a table, including those backing primary keys and unique
+/// constraints. Expression keys are listed as their SQL text where the
+/// database reports it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct IndexDef {
+    pub name: String,
+    pub columns: Vec<String>,
+    pub unique: bool,
+    pub primary: bool,
+    /// WHERE clause of a partial index.
+    pub predicate: Option<String>,
+    /// Access method, e.g. btree, gin, FULLTEXT.
+    pub method: Option<String>,
+}
+
+/// A UNIQUE constraint.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct UniqueDef {
+    pub name: String,
+    pub columns: Vec<String>,
+}
+
+/// A CHECK constraint; unnamed SQLite checks have no name.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CheckDef {
+    pub name: Option<String>,
+    pub expression: String,
 }
 
-/// A table or view definition with columns and foreign keys.
+/// A table or view definition with columns, keys and constraints.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TableDef {
     pub schema: String,
@@ -57,6 +98,12 @@ pub struct TableDef {
     pub type_: String,
     pub columns: Vec<ColumnDef>,
     pub foreign_keys: Vec<ForeignKeyDef>,
+    #[serde(default)]
+    pub indexes: Vec<IndexDef>,
+    #[serde(default)]
+    pub unique_constraints: Vec<UniqueDef>,
+    #[serde(default)]
+    pub checks: Vec<CheckDef>,
 }
 
 /// Top-level schema info for a database.
@@ -67,299 +114,1109 @@ pub struct DatabaseSchema {
     pub tables: Vec<TableDef>,
 }
 
-/// Retrieve schema for any supported dialect.
+/// Introspected schema per connection id; see [`super::cached_schema`].
+pub type SchemaCache = Arc<Mutex<HashMap<String, CachedSchema>>>;
+
+#[derive(Debug, Clone)]
+pub struct CachedSchema {
+    pub schema: Arc<DatabaseSchema>,
+    /// [`schema_version`] taken just before introspecting.
+    pub version: String,
+}
+
+/// Cheap fingerprint of the catalog that changes with DDL from any client:
+/// SQLite's `schema_version`, a hash over Postgres' tables and views with
+/// their columns, constraints and indexes, or over MySQL's tables and columns.
+pub async fn schema_version(pool: &DynPool) -> Result<String> {
+    let v = match pool {
+        DynPool::Sqlite(p) => {
+            let v: i64 = sqlx::query_scalar("PRAGMA schema_version").fetch_one(p).await?;
+            v.to_string()
+        }
+        // relfilenode moves when a table is rewritten (ALTER ... TYPE, and
+        // also TRUNCATE or VACUUM FULL), so those read as changes too.
+        // Temporary schemas are private to their sessions and left out.
+        DynPool::Postgres(p) => {
+            sqlx::query_scalar(
+                r#"
+                WITH rel AS (
+                    SELECT c.oid, c.relfilenode, c.relname, c.relkind
+                    FROM pg_class c JOIN pg_namespace n ON n.oid = c.relnamespace
+                    WHERE c.relkind IN ('r', 'p', 'v', 'm', 'f')
+                      AND n.nspname NOT IN ('pg_catalog','information_schema')
+                      AND n.nspname NOT LIKE 'pg_toast%' AND n.nspname NOT LIKE 'pg_temp%'
+                )
+                SELECT md5(concat(
+                    (SELECT string_agg(concat_ws(':', oid, relfilenode, relname, relkind), ',' ORDER BY oid) FROM rel),
+                    '|',
+                    (SELECT string_agg(concat_ws(':', a.attrelid, a.attnum, a.attname, a.atttypid, a.atttypmod,
+                                                 a.attnotnull, a.atthasdef, a.attisdropped), ',' ORDER BY a.attrelid, a.attnum)
+                     FROM pg_attribute a JOIN rel ON rel.oid = a.attrelid
+                     WHERE a.attnum > 0),
+                    '|',
+                    (SELECT string_agg(concat_ws(':', co.oid, co.conname), ',' ORDER BY co.oid)
+                     FROM pg_constraint co JOIN rel ON rel.oid = co.conrelid),
+                    '|',
+                    (SELECT string_agg(x.indexrelid::text, ',' ORDER BY x.indexrelid)
+                     FROM pg_index x JOIN rel ON rel.oid = x.indrelid)
+                ))
+                "#,
+            )
+            .fetch_one(p)
+            .await?
+        }
+        // update_time and create_time are left out: MySQL 8 caches both
+        // (information_schema_stats_expiry), update_time also follows row
+        // writes, and a table recreated as it was has the same schema
+        DynPool::MySql(p) => {
+            let sys = "('mysql','information_schema','performance_schema','sys')";
+            let base: String = sqlx::query_scalar(&format!(
+                r#"
+                SELECT CAST(CONCAT_WS(':',
+                    (SELECT CONCAT(COUNT(*), '/', COALESCE(SUM(CRC32(CONCAT_WS(':', table_schema, table_name, table_type))), 0))
+                     FROM information_schema.tables
+                     WHERE table_schema NOT IN {sys}),
+                    (SELECT CONCAT(COUNT(*), '/', COALESCE(SUM(CRC32(CONCAT_WS(':', table_schema, table_name, column_name,
+                                                                               ordinal_position, column_type, is_nullable, column_default))), 0))
+                     FROM information_schema.columns
+                     WHERE table_schema NOT IN {sys}),
+                    (SELECT CONCAT(COUNT(*), '/', COALESCE(SUM(CRC32(CONCAT_WS(':', table_schema, table_name, index_name,
+                                                                               seq_in_index, column_name, non_unique))), 0))
+                     FROM information_schema.statistics
+                     WHERE table_schema NOT IN {sys}),
+                    (SELECT CONCAT(COUNT(*), '/', COALESCE(SUM(CRC32(CONCAT_WS(':', k.table_schema, k.table_name, k.constraint_name,
+                                                                               k.ordinal_position, k.column_name, k.referenced_table_schema,
+                                                                               k.referenced_table_name, k.referenced_column_name,
+                                                                               r.update_rule, r.delete_rule))), 0))
+                     FROM information_schema.key_column_usage k
+                     JOIN information_schema.referential_constraints r
+                       ON r.constraint_schema = k.constraint_schema AND r.constraint_name = k.constraint_name
+                     WHERE k.table_schema NOT IN {sys} AND k.referenced_table_name IS NOT NULL)
+                ) AS CHAR)
+                "#
+            ))
+            .fetch_one(p)
+            .await?;
+            let checks: String = match sqlx::query_scalar(&format!(
+                r#"
+                SELECT CAST(CONCAT(COUNT(*), '/', COALESCE(SUM(CRC32(CONCAT_WS(':', constraint_schema, constraint_name, check_clause))), 0)) AS CHAR)
+                FROM information_schema.check_constraints
+                WHERE constraint_schema NOT IN {sys}
+                "#
+            ))
+            .fetch_one(p)
+            .await
+            {
+                Ok(v) => v,
+                Err(e) if no_such_view(&e) => String::new(),
+                Err(e) => return Err(e.into()),
+            };
+            format!("{base}:{checks}")
+        }
+    };
+    Ok(v)
+}
+
+// ER_UNKNOWN_TABLE from MySQL: an information_schema view the server does not
+// have, such as check_constraints before MySQL 8.0.16 and MariaDB 10.2.
+fn no_such_view(e: &sqlx::Error) -> bool {
+    match e {
+        sqlx::Error::Database(e) => {
+            e.try_downcast_ref::<sqlx::mysql::MySqlDatabaseError>().is_some_and(|e| e.number() == 1109)
+        }
+        _ => false,
+    }
+}
+
+/// Which tables and views to load. Patterns use LIKE syntax (`%`, `_`) and
+/// ignore case; an empty filter keeps everything.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ObjectFilter {
+    /// Exact schema names, e.g. `public`.
+    #[serde(default)]
+    pub schemas: Vec<String>,
+    #[serde(default)]
+    pub schema_pattern: Option<String>,
+    #[serde(default)]
+    pub name_pattern: Option<String>,
+}
+
+impl ObjectFilter {
+    fn keeps(&self, schema: &str, name: &str) -> bool {
+        (self.schemas.is_empty() || self.schemas.iter().any(|s| s == schema))
+            && self.schema_pattern.as_deref().is_none_or(|p| like(p, schema))
+            && self.name_pattern.as_deref().is_none_or(|p| like(p, name))
+    }
+}
+
+/// A table or view by name; without `schema` it matches in any schema.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ObjectRef {
+    #[serde(default)]
+    pub schema: Option<String>,
+    pub name: String,
+}
+
+/// Tables and views without their details, for browsing large databases.
+#[derive(Debug, Clone, Serialize)]
+pub struct ObjectList {
+    pub dialect: String,
+    /// Schemas holding at least one listed object.
+    pub schemas: Vec<String>,
+    pub objects: Vec<ObjectInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ObjectInfo {
+    pub schema: String,
+    pub name: String,
+    pub kind: ObjectKind,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ObjectKind {
+    Table,
+    View,
+}
+
+// Objects to introspect.
+enum Scope<'a> {
+    All,
+    Filter(&'a ObjectFilter),
+    Tables(&'a [ObjectRef]),
+}
+
+impl Scope<'_> {
+    fn keeps(&self, schema: &str, name: &str) -> bool {
+        match self {
+            Scope::All => true,
+            Scope::Filter(f) => f.keeps(schema, name),
+            Scope::Tables(refs) => refs
+                .iter()
+                .any(|r| r.name == name && r.schema.as_deref().is_none_or(|s| s == schema)),
+        }
+    }
+}
+
+/// Retrieve schema for any supported dialect, one catalog query per kind of
+/// detail for the whole database rather than per table.
 pub async fn inspect_schema(pool: &DynPool) -> Result<DatabaseSchema> {
+    inspect(pool, &Scope::All).await
+}
+
+/// [`inspect_schema`] limited to the tables and views `filter` keeps; the
+/// detail queries only read those tables.
+pub async fn inspect_filtered(pool: &DynPool, filter: &ObjectFilter) -> Result<DatabaseSchema> {
+    inspect(pool, &Scope::Filter(filter)).await
+}
+
+/// Full definitions of the named tables in listing order; unknown names are
+/// left out.
+pub async fn describe_tables(pool: &DynPool, tables: &[ObjectRef]) -> Result<Vec<TableDef>> {
+    Ok(inspect(pool, &Scope::Tables(tables)).await?.tables)
+}
+
+/// Schemas, tables and views from a single catalog query.
+pub async fn list_objects(pool: &DynPool, filter: &ObjectFilter) -> Result<ObjectList> {
+    let tables = list(pool, &Scope::Filter(filter)).await?;
+    let objects: Vec<ObjectInfo> = tables
+        .list
+        .into_iter()
+        .map(|t| ObjectInfo {
+            // 'VIEW', 'SYSTEM VIEW' or SQLite's 'view'; the rest are tables
+            kind: if t.type_.to_uppercase().contains("VIEW") { ObjectKind::View } else { ObjectKind::Table },
+            schema: t.schema,
+            name: t.name,
+        })
+        .collect();
+    let mut schemas: Vec<String> = objects.iter().map(|o| o.schema.clone()).collect();
+    schemas.dedup();
+    Ok(ObjectList { dialect: dialect_name(pool.dialect()).into(), schemas, objects })
+}
+
+fn dialect_name(d: Dialect) -> &'static str {
+    match d {
+        Dialect::Sqlite => "sqlite",
+        Dialect::Postgres => "postgres",
+        Dialect::MySql => "mysql",
+    }
+}
+
+async fn inspect(pool: &DynPool, scope: &Scope<'_>) -> Result<DatabaseSchema> {
+    let mut tables = list(pool, scope).await?;
+    // anything narrower than the whole database restricts the detail queries
+    // to the listed tables
+    let narrow = !matches!(scope, Scope::All);
     match pool {
-        DynPool::Sqlite(p) => inspect_sqlite(p).await,
-        DynPool::Postgres(p) => inspect_postgres(p).await,
-        DynPool::MySql(p) => inspect_mysql(p).await,
+        DynPool::Sqlite(p) => describe_sqlite(p, &mut tables, narrow).await?,
+        DynPool::Postgres(p) => describe_postgres(p, &mut tables, narrow).await?,
+        DynPool::MySql(p) => describe_mysql(p, &mut tables, narrow).await?,
     }
+    Ok(tables.into_schema(dialect_name(pool.dialect())))
 }
 
-async fn inspect_sqlite(pool: &Pool<sqlx::Sqlite>) -> Result<DatabaseSchema> {
-    let tv = sqlx::query(
-        "SELECT name, type FROM sqlite_master WHERE type IN ('table','view') AND name NOT LIKE 'sqlite_%' ORDER BY name;"
-    )
-    .fetch_all(pool)
-    .await?;
+// Tables and views in scope ordered by schema and name, without details.
+async fn list(pool: &DynPool, scope: &Scope<'_>) -> Result<Tables> {
+    let rows: Vec<(String, String, String)> = match pool {
+        DynPool::Sqlite(p) => {
+            sqlx::query_as(
+                "SELECT 'main', name, type FROM sqlite_master WHERE type IN ('table','view') AND name NOT LIKE 'sqlite_%' ORDER BY name",
+            )
+            .fetch_all(p)
+            .await?
+        }
+        DynPool::Postgres(p) => {
+            sqlx::query_as(
+                r#"
+                SELECT table_schema::text, table_name::text, table_type::text
+                FROM information_schema.tables
+                WHERE table_schema NOT IN ('pg_catalog','information_schema')
+                ORDER BY table_schema, table_name
+                "#,
+            )
+            .fetch_all(p)
+            .await?
+        }
+        DynPool::MySql(p) => {
+            sqlx::query_as(
+                r#"
+                SELECT table_schema AS table_schema, table_name AS table_name, table_type AS table_type
+                FROM information_schema.tables
+                WHERE table_schema NOT IN ('mysql','information_schema','performance_schema','sys')
+                ORDER BY table_schema, table_name
+                "#,
+            )
+            .fetch_all(p)
+            .await?
+        }
+    };
+    let mut tables = Tables::new();
+    for (schema, name, type_) in rows {
+        if scope.keeps(&schema, &name) {
+            tables.add(schema, name, type_);
+        }
+    }
+    Ok(tables)
+}
 
-    let mut tables = Vec::new();
-    for row in tv {
-        let name: String = row.try_get("name")?;
-        let type_: String = row.try_get("type")?;
+/// Primary key columns of `table` in key order; empty if it has none.
+/// The table is resolved like an unqualified name in a query.
+pub async fn primary_key(pool: &DynPool, schema: Option<&str>, table: &str) -> Result<Vec<String>> {
+    let cols = match pool {
+        DynPool::Sqlite(p) => {
+            sqlx::query_scalar("SELECT name FROM pragma_table_info(?, ?) WHERE pk > 0 ORDER BY pk")
+                .bind(table)
+                .bind(schema.unwrap_or("main"))
+                .fetch_all(p)
+                .await?
+        }
+        DynPool::Postgres(p) => {
+            sqlx::query_scalar(
+                r#"
+                SELECT a.attname::text
+                FROM pg_index i
+                JOIN pg_attribute a ON a.attrelid = i.indrelid AND a.attnum = ANY(i.indkey)
+                WHERE i.indrelid = to_regclass(coalesce(quote_ident($2) || '.', '') || quote_ident($1))
+                  AND i.indisprimary
+                ORDER BY array_position(i.indkey::int2[], a.attnum)
+                "#,
+            )
+            .bind(table)
+            .bind(schema)
+            .fetch_all(p)
+            .await?
+        }
+        DynPool::MySql(p) => {
+            sqlx::query_scalar(
+                r#"
+                SELECT COLUMN_NAME
+                FROM information_schema.KEY_COLUMN_USAGE
+                WHERE TABLE_SCHEMA = COALESCE(?, DATABASE()) AND TABLE_NAME = ? AND CONSTRAINT_NAME = 'PRIMARY'
+                ORDER BY ORDINAL_POSITION
+                "#,
+            )
+            .bind(schema)
+            .bind(table)
+            .fetch_all(p)
+            .await?
+        }
+    };
+    Ok(cols)
+}
 
-        let cols_rows = sqlx::query(&format!("PRAGMA table_info('{}');", name.replace("'", "''")))
-            .fetch_all(pool)
-            .await?;
-        let columns = cols_rows
-            .into_iter()
-            .map(|r| {
-                let dt: String = r.try_get::<String, _>("type").unwrap_or_default();
-                let (len_opt, prec_opt, scale_opt) = parse_len_prec_scale(&dt);
-                ColumnDef {
-                    name: r.try_get::<String, _>("name").unwrap_or_default(),
-                    data_type: dt,
-                    nullable: !(r.try_get::<i64, _>("notnull").unwrap_or(0) == 1),
-                    default: r.try_get::<Option<String>, _>("dflt_value").unwrap_or(None),
-                    is_pk: r.try_get::<i64, _>("pk").unwrap_or(0) == 1,
-                    length: len_opt,
-                    precision: prec_opt,
-                    scale: scale_opt,
-                }
-            })
-            .collect();
+// Tables being assembled from whole-database catalog queries; rows are
+// matched to their table by schema and name.
+struct Tables {
+    list: Vec<TableDef>,
+    index: HashMap<(String, String), usize>,
+}
 
-        let fk_rows = sqlx::query(&format!("PRAGMA foreign_key_list('{}');", name.replace("'", "''")))
-            .fetch_all(pool)
-            .await?;
-        let foreign_keys = fk_rows
-            .into_iter()
-            .map(|r| ForeignKeyDef {
-                from: r.try_get::<String, _>("from").unwrap_or_default(),
-                ref_schema: None,
-                to_table: r.try_get::<String, _>("table").unwrap_or_default(),
-                to: r.try_get::<String, _>("to").unwrap_or_default(),
-            })
-            .collect();
+impl Tables {
+    fn new() -> Self {
+        Tables { list: Vec::new(), index: HashMap::new() }
+    }
 
-        tables.push(TableDef {
-            schema: "main".into(),
+    fn add(&mut self, schema: String, name: String, type_: String) -> &mut TableDef {
+        self.index.insert((schema.clone(), name.clone()), self.list.len());
+        self.list.push(TableDef {
+            schema,
             name,
             type_,
-            columns,
-            foreign_keys,
+            columns: vec![],
+            foreign_keys: vec![],
+            indexes: vec![],
+            unique_constraints: vec![],
+            checks: vec![],
         });
+        self.list.last_mut().unwrap()
     }
 
-    Ok(DatabaseSchema {
-        dialect: "sqlite".into(),
-        schemas: vec!["main".into()],
-        tables,
-    })
+    // None for rows of tables missing from the listing, e.g. created since.
+    fn get(&mut self, schema: &str, name: &str) -> Option<&mut TableDef> {
+        let i = *self.index.get(&(schema.to_string(), name.to_string()))?;
+        Some(&mut self.list[i])
+    }
+
+    fn into_schema(self, dialect: &str) -> DatabaseSchema {
+        let mut schemas: Vec<String> = self.list.iter().map(|t| t.schema.clone()).collect();
+        schemas.sort();
+        schemas.dedup();
+        DatabaseSchema { dialect: dialect.into(), schemas, tables: self.list }
+    }
 }
 
-async fn inspect_postgres(pool: &Pool<sqlx::Postgres>) -> Result<DatabaseSchema> {
-    let tv = sqlx::query(
+async fn describe_sqlite(pool: &Pool<sqlx::Sqlite>, tables: &mut Tables, narrow: bool) -> Result<()> {
+    // ?1 is a JSON array of the listed table names, or NULL for all
+    let only: Option<String> = if narrow {
+        Some(serde_json::to_string(&tables.list.iter().map(|t| &t.name).collect::<Vec<_>>())?)
+    } else {
+        None
+    };
+
+    let sql_rows = sqlx::query(
         r#"
-        SELECT table_schema, table_name, table_type
-        FROM information_schema.tables
-        WHERE table_schema NOT IN ('pg_catalog','information_schema')
-        ORDER BY table_schema, table_name
-        "#
+        SELECT m.name, m.sql FROM sqlite_master m
+        WHERE m.type = 'table' AND (?1 IS NULL OR m.name IN (SELECT value FROM json_each(?1)))
+        "#,
     )
+    .bind(&only)
     .fetch_all(pool)
     .await?;
+    for r in sql_rows {
+        let Some(t) = tables.get("main", &r.try_get::<String, _>("name")?) else {
+            continue;
+        };
+        // SQLite keeps CHECK constraints only in the CREATE TABLE text
+        let sql: Option<String> = r.try_get("sql")?;
+        t.checks = sql.as_deref().map(sqlite_checks).unwrap_or_default();
+    }
 
-    let mut tables = Vec::new();
-    for row in tv {
-        let schema: String = row.try_get("table_schema")?;
-        let name: String = row.try_get("table_name")?;
-        let type_: String = row.try_get("table_type")?;
-
-        let cols_rows = sqlx::query(
-            r#"
-            SELECT a.attname AS column_name,
-                   t.typname AS data_type,
-                   NOT a.attnotnull AS nullable,
-                   pg_get_expr(ad.adbin, ad.adrelid) AS default_value,
-                   EXISTS (
-                       SELECT 1 FROM pg_index i
-                       WHERE i.indrelid = a.attrelid
-                         AND a.attnum = ANY(i.indkey)
-                         AND i.indisprimary
-                   ) AS is_pk,
-                   information_schema.character_maximum_length(a.attrelid::regclass::text, a.attnum) AS char_len,
-                   information_schema.numeric_precision(a.attrelid::regclass::text, a.attnum) AS num_precision,
-                   information_schema.numeric_scale(a.attrelid::regclass::text, a.attnum) AS num_scale
-            FROM pg_attribute a
-            JOIN pg_class c ON c.oid = a.attrelid
-            JOIN pg_type t ON t.oid = a.atttypid
-            LEFT JOIN pg_attrdef ad ON ad.adrelid = a.attrelid AND ad.adnum = a.attnum
-            JOIN pg_namespace n ON n.oid = c.relnamespace
-            WHERE a.attnum > 0 AND NOT a.attisdropped AND n.nspname = $1 AND c.relname = $2
-            ORDER BY a.attnum
-            "#
-        )
-        .bind(&schema)
-        .bind(&name)
-        .fetch_all(pool)
-        .await?;
-        let columns = cols_rows
-            .into_iter()
-            .map(|r| ColumnDef {
-                name: r.try_get::<String, _>("column_name").unwrap_or_default(),
-                data_type: r.try_get::<String, _>("data_type").unwrap_or_default(),
-                nullable: r.try_get::<bool, _>("nullable").unwrap_or(true),
-                default: r.try_get::<Option<String>, _>("default_value").unwrap_or(None),
-                is_pk: r.try_get::<bool, _>("is_pk").unwrap_or(false),
-                length: r
-                    .try_get::<Option<i32>, _>("char_len")
-                    .ok()
-                    .flatten()
-                    .map(|v| v as u32),
-                precision: r
-                    .try_get::<Option<i32>, _>("num_precision")
-                    .ok()
-                    .flatten()
-                    .map(|v| v as u32),
-                scale: r
-                    .try_get::<Option<i32>, _>("num_scale")
-                    .ok()
-                    .flatten()
-                    .map(|v| v as u32),
-            })
-            .collect();
+    // the table-valued pragmas join against sqlite_master, so each kind of
+    // detail is one query for the whole database
+    let cols_rows = sqlx::query(
+        r#"
+        SELECT m.name AS tbl, p.name, p.type, p."notnull", p.dflt_value, p.pk
+        FROM sqlite_master m JOIN pragma_table_info(m.name) p
+        WHERE m.type IN ('table','view') AND m.name NOT LIKE 'sqlite_%'
+          AND (?1 IS NULL OR m.name IN (SELECT value FROM json_each(?1)))
+        ORDER BY m.name, p.cid
+        "#,
+    )
+    .bind(&only)
+    .fetch_all(pool)
+    .await?;
+    for r in cols_rows {
+        let Some(t) = tables.get("main", &r.try_get::<String, _>("tbl")?) else { continue };
+        let dt: String = r.try_get::<String, _>("type").unwrap_or_default();
+        let (len_opt, prec_opt, scale_opt) = parse_len_prec_scale(&dt);
+        t.columns.push(ColumnDef {
+            name: r.try_get::<String, _>("name").unwrap_or_default(),
+            data_type: dt,
+            nullable: r.try_get::<i64, _>("notnull").unwrap_or(0) != 1,
+            default: r.try_get::<Option<String>, _>("dflt_value").unwrap_or(None),
+            is_pk: r.try_get::<i64, _>("pk").unwrap_or(0) > 0,
+            length: len_opt,
+            precision: prec_opt,
+            scale: scale_opt,
+        });
+    }
 
-        let fk_rows = sqlx::query(
-            r#"
-            SELECT kcu.column_name AS "from",
-                   ccu.table_schema AS ref_schema,
-                   ccu.table_name AS to_table,
-                   ccu.column_name AS "to"
-            FROM information_schema.table_constraints tc
-            JOIN information_schema.key_column_usage kcu
-              ON tc.constraint_name = kcu.constraint_name AND tc.table_schema = kcu.table_schema
-            JOIN information_schema.constraint_column_usage ccu
-              ON ccu.constraint_name = tc.constraint_name AND ccu.constraint_schema = tc.table_schema
-            WHERE tc.constraint_type = 'FOREIGN KEY' AND tc.table_schema = $1 AND tc.table_name = $2
-            "#
-        )
-        .bind(&schema)
-        .bind(&name)
-        .fetch_all(pool)
-        .await?;
-        let foreign_keys = fk_rows
-            .into_iter()
-            .map(|r| ForeignKeyDef {
-                from: r.try_get::<String, _>("from").unwrap_or_default(),
-                ref_schema: Some(r.try_get::<String, _>("ref_schema").unwrap_or_default()),
-                to_table: r.try_get::<String, _>("to_table").unwrap_or_default(),
-                to: r.try_get::<String, _>("to").unwrap_or_default(),
-            })
-            .collect();
+    // one row per column; `id` groups the columns of a constraint, `seq` orders them
+    let fk_rows = sqlx::query(
+        r#"
+        SELECT m.name AS tbl, f.id, f."table", f."from", f."to", f.on_update, f.on_delete
+        FROM sqlite_master m JOIN pragma_foreign_key_list(m.name) f
+        WHERE m.type = 'table' AND m.name NOT LIKE 'sqlite_%'
+          AND (?1 IS NULL OR m.name IN (SELECT value FROM json_each(?1)))
+        ORDER BY m.name, f.id, f.seq
+        "#,
+    )
+    .bind(&only)
+    .fetch_all(pool)
+    .await?;
+    let mut last: Option<(String, i64)> = None;
+    for r in fk_rows {
+        let tbl: String = r.try_get("tbl")?;
+        let id: i64 = r.try_get("id").unwrap_or_default();
+        let Some(t) = tables.get("main", &tbl) else { continue };
+        let column: String = r.try_get("from").unwrap_or_default();
+        let to: Option<String> = r.try_get("to").unwrap_or(None);
+        let key = Some((tbl, id));
+        if last == key {
+            let fk = t.foreign_keys.last_mut().unwrap();
+            fk.columns.push(column);
+            fk.ref_columns.extend(to);
+            continue;
+        }
+        last = key;
+        t.foreign_keys.push(ForeignKeyDef {
+            name: None,
+            columns: vec![column],
+            ref_schema: None,
+            to_table: r.try_get("table").unwrap_or_default(),
+            ref_columns: to.into_iter().collect(),
+            on_update: r.try_get("on_update").ok(),
+            on_delete: r.try_get("on_delete").ok(),
+        });
+    }
+    // `REFERENCES parent` without columns means the parent's primary key
+    let mut pks: HashMap<String, Vec<String>> = tables
+        .list
+        .iter()
+        .map(|t| {
+            let pk = t.columns.iter().filter(|c| c.is_pk).map(|c| c.name.clone()).collect();
+            (t.name.to_lowercase(), pk)
+        })
+        .collect();
+    for fk in tables.list.iter_mut().flat_map(|t| &mut t.foreign_keys) {
+        if !fk.ref_columns.is_empty() {
+            continue;
+        }
+        let parent = fk.to_table.to_lowercase();
+        // a narrowed describe may not include the parent
+        if !pks.contains_key(&parent) {
+            let pk: Vec<String> =
+                sqlx::query_scalar("SELECT name FROM pragma_table_info(?1) WHERE pk > 0 ORDER BY pk")
+                    .bind(&fk.to_table)
+                    .fetch_all(pool)
+                    .await?;
+            pks.insert(parent.clone(), pk);
+        }
+        fk.ref_columns = pks[&parent].clone();
+    }
 
-        tables.push(TableDef {
-            schema,
+    // expression keys have no name in index_info
+    let index_rows = sqlx::query(
+        r#"
+        SELECT m.name AS tbl, il.name AS idx, il."unique", il.origin, il.partial,
+               coalesce(ii.name, '(expression)') AS col, s.sql
+        FROM sqlite_master m
+        JOIN pragma_index_list(m.name) il
+        JOIN pragma_index_info(il.name) ii
+        LEFT JOIN sqlite_master s ON s.type = 'index' AND s.name = il.name
+        WHERE m.type = 'table' AND m.name NOT LIKE 'sqlite_%'
+          AND (?1 IS NULL OR m.name IN (SELECT value FROM json_each(?1)))
+        ORDER BY m.name, il.name, ii.seqno
+        "#,
+    )
+    .bind(&only)
+    .fetch_all(pool)
+    .await?;
+    // UNIQUE constraints show up as indexes with origin `u`
+    let mut unique: Vec<(String, usize)> = Vec::new();
+    for r in index_rows {
+        let tbl: String = r.try_get("tbl")?;
+        let Some(t) = tables.get("main", &tbl) else { continue };
+        let name: String = r.try_get("idx")?;
+        let column: String = r.try_get("col")?;
+        // index names are unique per database, so a repeat continues the key
+        match t.indexes.last_mut() {
+            Some(ix) if ix.name == name => {
+                ix.columns.push(column);
+                continue;
+            }
+            _ => {}
+        }
+        let origin: String = r.try_get("origin")?;
+        let predicate = if r.try_get::<i64, _>("partial")? == 1 {
+            r.try_get::<Option<String>, _>("sql")?.as_deref().and_then(index_predicate)
+        } else {
+            None
+        };
+        if origin == "u" {
+            unique.push((tbl, t.indexes.len()));
+        }
+        t.indexes.push(IndexDef {
             name,
-            type_,
-            columns,
-            foreign_keys,
+            columns: vec![column],
+            unique: r.try_get::<i64, _>("unique")? == 1,
+            primary: origin == "pk",
+            predicate,
+            method: Some("btree".into()),
         });
     }
+    for (tbl, i) in unique {
+        if let Some(t) = tables.get("main", &tbl) {
+            let ix = &t.indexes[i];
+            let u = UniqueDef { name: ix.name.clone(), columns: ix.columns.clone() };
+            t.unique_constraints.push(u);
+        }
+    }
 
-    let schemas: Vec<String> = tables.iter().map(|t| t.schema.clone()).collect();
-
-    Ok(DatabaseSchema {
-        dialect: "postgres".into(),
-        schemas,
-        tables,
-    })
+    Ok(())
 }
 
-async fn inspect_mysql(pool: &Pool<sqlx::MySql>) -> Result<DatabaseSchema> {
-    let tv = sqlx::query(
+async fn describe_postgres(pool: &Pool<sqlx::Postgres>, tables: &mut Tables, narrow: bool) -> Result<()> {
+    // $1 and $2 pair up the listed schemas and table names, or are NULL for all
+    let (only_schemas, only_names): (Option<Vec<String>>, Option<Vec<String>>) = if narrow {
+        let (s, n) = tables.list.iter().map(|t| (t.schema.clone(), t.name.clone())).unzip();
+        (Some(s), Some(n))
+    } else {
+        (None, None)
+    };
+
+    let cols_rows = sqlx::query(
         r#"
-        SELECT table_schema, table_name, table_type
-        FROM information_schema.tables
-        WHERE table_schema NOT IN ('mysql','information_schema','performance_schema','sys')
-        ORDER BY table_schema, table_name
+        SELECT n.nspname::text AS table_schema,
+               c.relname::text AS table_name,
+               a.attname::text AS column_name,
+               t.typname::text AS data_type,
+               NOT a.attnotnull AS nullable,
+               pg_get_expr(ad.adbin, ad.adrelid) AS default_value,
+               EXISTS (
+                   SELECT 1 FROM pg_index i
+                   WHERE i.indrelid = a.attrelid
+                     AND a.attnum = ANY(i.indkey)
+                     AND i.indisprimary
+               ) AS is_pk,
+               information_schema._pg_char_max_length(a.atttypid, a.atttypmod) AS char_len,
+               information_schema._pg_numeric_precision(a.atttypid, a.atttypmod) AS num_precision,
+               information_schema._pg_numeric_scale(a.atttypid, a.atttypmod) AS num_scale
+        FROM pg_attribute a
+        JOIN pg_class c ON c.oid = a.attrelid
+        JOIN pg_type t ON t.oid = a.atttypid
+        LEFT JOIN pg_attrdef ad ON ad.adrelid = a.attrelid AND ad.adnum = a.attnum
+        JOIN pg_namespace n ON n.oid = c.relnamespace
+        WHERE a.attnum > 0 AND NOT a.attisdropped
+          AND c.relkind IN ('r', 'p', 'v', 'm', 'f')
+          AND n.nspname NOT IN ('pg_catalog','information_schema') AND n.nspname NOT LIKE 'pg_toast%'
+          AND ($1::text[] IS NULL OR (n.nspname, c.relname) IN (SELECT * FROM unnest($1::text[], $2::text[])))
+        ORDER BY n.nspname, c.relname, a.attnum
         "#
     )
+    .bind(&only_schemas)
+    .bind(&only_names)
     .fetch_all(pool)
     .await?;
+    for r in cols_rows {
+        let Some(t) = tables.get(&r.try_get::<String, _>("table_schema")?, &r.try_get::<String, _>("table_name")?) else {
+            continue;
+        };
+        t.columns.push(ColumnDef {
+            name: r.try_get::<String, _>("column_name").unwrap_or_default(),
+            data_type: r.try_get::<String, _>("data_type").unwrap_or_default(),
+            nullable: r.try_get::<bool, _>("nullable").unwrap_or(true),
+            default: r.try_get::<Option<String>, _>("default_value").unwrap_or(None),
+            is_pk: r.try_get::<bool, _>("is_pk").unwrap_or(false),
+            length: r
+                .try_get::<Option<i32>, _>("char_len")
+                .ok()
+                .flatten()
+                .map(|v| v as u32),
+            precision: r
+                .try_get::<Option<i32>, _>("num_precision")
+                .ok()
+                .flatten()
+                .map(|v| v as u32),
+            scale: r
+                .try_get::<Option<i32>, _>("num_scale")
+                .ok()
+                .flatten()
+                .map(|v| v as u32),
+        });
+    }
 
-    let mut tables = Vec::new();
-    for row in tv {
-        let schema: String = row.try_get("table_schema")?;
-        let name: String = row.try_get("table_name")?;
-        let type_: String = row.try_get("table_type")?;
+    // conkey/confkey pair the columns up in constraint order
+    let fk_rows = sqlx::query(
+        r#"
+        SELECT n.nspname::text AS table_schema,
+               t.relname::text AS table_name,
+               c.conname::text AS name,
+               ARRAY(SELECT a.attname::text
+                     FROM unnest(c.conkey) WITH ORDINALITY AS k(attnum, ord)
+                     JOIN pg_attribute a ON a.attrelid = c.conrelid AND a.attnum = k.attnum
+                     ORDER BY k.ord) AS columns,
+               rn.nspname::text AS ref_schema,
+               rt.relname::text AS to_table,
+               ARRAY(SELECT a.attname::text
+                     FROM unnest(c.confkey) WITH ORDINALITY AS k(attnum, ord)
+                     JOIN pg_attribute a ON a.attrelid = c.confrelid AND a.attnum = k.attnum
+                     ORDER BY k.ord) AS ref_columns,
+               c.confupdtype::text AS on_update,
+               c.confdeltype::text AS on_delete
+        FROM pg_constraint c
+        JOIN pg_class t ON t.oid = c.conrelid
+        JOIN pg_namespace n ON n.oid = t.relnamespace
+        JOIN pg_class rt ON rt.oid = c.confrelid
+        JOIN pg_namespace rn ON rn.oid = rt.relnamespace
+        WHERE c.contype = 'f' AND n.nspname NOT IN ('pg_catalog','information_schema')
+          AND ($1::text[] IS NULL OR (n.nspname, t.relname) IN (SELECT * FROM unnest($1::text[], $2::text[])))
+        ORDER BY n.nspname, t.relname, c.conname
+        "#
+    )
+    .bind(&only_schemas)
+    .bind(&only_names)
+    .fetch_all(pool)
+    .await?;
+    for r in fk_rows {
+        let Some(t) = tables.get(&r.try_get::<String, _>("table_schema")?, &r.try_get::<String, _>("table_name")?) else {
+            continue;
+        };
+        t.foreign_keys.push(ForeignKeyDef {
+            name: r.try_get("name").ok(),
+            columns: r.try_get("columns").unwrap_or_default(),
+            ref_schema: r.try_get("ref_schema").ok(),
+            to_table: r.try_get("to_table").unwrap_or_default(),
+            ref_columns: r.try_get("ref_columns").unwrap_or_default(),
+            on_update: r.try_get::<String, _>("on_update").ok().map(|a| pg_fk_action(&a)),
+            on_delete: r.try_get::<String, _>("on_delete").ok().map(|a| pg_fk_action(&a)),
+        });
+    }
 
-        let cols_rows = sqlx::query(
-            r#"
-            SELECT column_name, data_type, is_nullable, column_default, column_key,
-                   character_maximum_length AS char_len,
-                   numeric_precision AS num_precision,
-                   numeric_scale AS num_scale
-            FROM information_schema.columns
-            WHERE table_schema = ? AND table_name = ?
-            ORDER BY ordinal_position
-            "#
-        )
-        .bind(&schema)
-        .bind(&name)
-        .fetch_all(pool)
-        .await?;
-        let columns = cols_rows
-            .into_iter()
-            .map(|r| {
-                let key: String = r.try_get("column_key").unwrap_or_default();
-                ColumnDef {
-                    name: r.try_get::<String, _>("column_name").unwrap_or_default(),
-                    data_type: r.try_get::<String, _>("data_type").unwrap_or_default(),
-                    nullable: r.try_get::<String, _>("is_nullable").unwrap_or_else(|_| "YES".into()) == "YES",
-                    default: r.try_get::<Option<String>, _>("column_default").unwrap_or(None),
-                    is_pk: key == "PRI",
-                    length: r
-                        .try_get::<Option<i64>, _>("char_len")
-                        .ok()
-                        .flatten()
-                        .map(|v| v as u32),
-                    precision: r
-                        .try_get::<Option<i64>, _>("num_precision")
-                        .ok()
-                        .flatten()
-                        .map(|v| v as u32),
-                    scale: r
-                        .try_get::<Option<i64>, _>("num_scale")
-                        .ok()
-                        .flatten()
-                        .map(|v| v as u32),
-                }
-            })
-            .collect();
+    let index_rows = sqlx::query(
+        r#"
+        SELECT n.nspname::text AS table_schema,
+               t.relname::text AS table_name,
+               i.relname::text AS name,
+               ARRAY(SELECT pg_get_indexdef(x.indexrelid, k, true)
+                     FROM generate_series(1, x.indnkeyatts) AS k ORDER BY k) AS columns,
+               x.indisunique AS is_unique,
+               x.indisprimary AS is_primary,
+               pg_get_expr(x.indpred, x.indrelid, true) AS predicate,
+               am.amname::text AS method
+        FROM pg_index x
+        JOIN pg_class i ON i.oid = x.indexrelid
+        JOIN pg_class t ON t.oid = x.indrelid
+        JOIN pg_namespace n ON n.oid = t.relnamespace
+        JOIN pg_am am ON am.oid = i.relam
+        WHERE n.nspname NOT IN ('pg_catalog','information_schema') AND n.nspname NOT LIKE 'pg_toast%'
+          AND ($1::text[] IS NULL OR (n.nspname, t.relname) IN (SELECT * FROM unnest($1::text[], $2::text[])))
+        ORDER BY n.nspname, t.relname, i.relname
+        "#,
+    )
+    .bind(&only_schemas)
+    .bind(&only_names)
+    .fetch_all(pool)
+    .await?;
+    for r in index_rows {
+        let Some(t) = tables.get(&r.try_get::<String, _>("table_schema")?, &r.try_get::<String, _>("table_name")?) else {
+            continue;
+        };
+        t.indexes.push(IndexDef {
+            name: r.try_get("name").unwrap_or_default(),
+            columns: r.try_get("columns").unwrap_or_default(),
+            unique: r.try_get("is_unique").unwrap_or(false),
+            primary: r.try_get("is_primary").unwrap_or(false),
+            predicate: r.try_get("predicate").unwrap_or(None),
+            method: r.try_get("method").ok(),
+        });
+    }
 
-        let fk_rows = sqlx::query(
-            r#"
-            SELECT column_name AS `from`,
-                   referenced_table_schema AS ref_schema,
-                   referenced_table_name AS to_table,
-                   referenced_column_name AS `to`
-            FROM information_schema.KEY_COLUMN_USAGE
-            WHERE table_schema = ? AND table_name = ? AND referenced_table_name IS NOT NULL
-            "#
-        )
-        .bind(&schema)
-        .bind(&name)
-        .fetch_all(pool)
-        .await?;
-        let foreign_keys = fk_rows
-            .into_iter()
-            .map(|r| ForeignKeyDef {
-                from: r.try_get::<String, _>("from").unwrap_or_default(),
+    let con_rows = sqlx::query(
+        r#"
+        SELECT n.nspname::text AS table_schema,
+               t.relname::text AS table_name,
+               c.conname::text AS name,
+               c.contype::text AS kind,
+               ARRAY(SELECT a.attname::text
+                     FROM unnest(c.conkey) WITH ORDINALITY AS k(attnum, ord)
+                     JOIN pg_attribute a ON a.attrelid = c.conrelid AND a.attnum = k.attnum
+                     ORDER BY k.ord) AS columns,
+               pg_get_constraintdef(c.oid, true) AS def
+        FROM pg_constraint c
+        JOIN pg_class t ON t.oid = c.conrelid
+        JOIN pg_namespace n ON n.oid = t.relnamespace
+        WHERE c.contype IN ('u', 'c') AND n.nspname NOT IN ('pg_catalog','information_schema')
+          AND ($1::text[] IS NULL OR (n.nspname, t.relname) IN (SELECT * FROM unnest($1::text[], $2::text[])))
+        ORDER BY n.nspname, t.relname, c.conname
+        "#,
+    )
+    .bind(&only_schemas)
+    .bind(&only_names)
+    .fetch_all(pool)
+    .await?;
+    for r in con_rows {
+        let Some(t) = tables.get(&r.try_get::<String, _>("table_schema")?, &r.try_get::<String, _>("table_name")?) else {
+            continue;
+        };
+        let name: String = r.try_get("name").unwrap_or_default();
+        if r.try_get::<String, _>("kind").unwrap_or_default() == "u" {
+            t.unique_constraints.push(UniqueDef { name, columns: r.try_get("columns").unwrap_or_default() });
+        } else {
+            // pg_get_constraintdef gives `CHECK (expr)`, plus NOT VALID when unvalidated
+            let def: String = r.try_get("def").unwrap_or_default();
+            let expression = def.strip_prefix("CHECK ").unwrap_or(&def).to_string();
+            t.checks.push(CheckDef { name: Some(name), expression });
+        }
+    }
+
+    Ok(())
+}
+
+async fn describe_mysql(pool: &Pool<sqlx::MySql>, tables: &mut Tables, narrow: bool) -> Result<()> {
+    let (pairs, binds) = mysql_pairs(tables, narrow);
+    let only = |schema_col: &str, table_col: &str| match &pairs {
+        None => String::new(),
+        Some(p) if p.is_empty() => " AND FALSE".into(),
+        Some(p) => format!(" AND ({schema_col}, {table_col}) IN ({p})"),
+    };
+
+    let cols_rows = bind_all(sqlx::query(&format!(
+        r#"
+        SELECT table_schema AS table_schema, table_name AS table_name,
+               column_name AS column_name, data_type AS data_type, is_nullable AS is_nullable,
+               column_default AS column_default, column_key AS column_key,
+               character_maximum_length AS char_len,
+               numeric_precision AS num_precision,
+               numeric_scale AS num_scale
+        FROM information_schema.columns
+        WHERE table_schema NOT IN ('mysql','information_schema','performance_schema','sys'){}
+        ORDER BY table_schema, table_name, ordinal_position
+        "#,
+        only("table_schema", "table_name")
+    )), &binds)
+    .fetch_all(pool)
+    .await?;
+    for r in cols_rows {
+        let Some(t) = tables.get(&r.try_get::<String, _>("table_schema")?, &r.try_get::<String, _>("table_name")?) else {
+            continue;
+        };
+        let key: String = r.try_get("column_key").unwrap_or_default();
+        t.columns.push(ColumnDef {
+            name: r.try_get::<String, _>("column_name").unwrap_or_default(),
+            data_type: r.try_get::<String, _>("data_type").unwrap_or_default(),
+            nullable: r.try_get::<String, _>("is_nullable").unwrap_or_else(|_| "YES".into()) == "YES",
+            default: r.try_get::<Option<String>, _>("column_default").unwrap_or(None),
+            is_pk: key == "PRI",
+            length: r
+                .try_get::<Option<i64>, _>("char_len")
+                .ok()
+                .flatten()
+                .map(|v| v as u32),
+            precision: r
+                .try_get::<Option<i64>, _>("num_precision")
+                .ok()
+                .flatten()
+                .map(|v| v as u32),
+            scale: r
+                .try_get::<Option<i64>, _>("num_scale")
+                .ok()
+                .flatten()
+                .map(|v| v as u32),
+        });
+    }
+
+    let fk_rows = bind_all(sqlx::query(&format!(
+        r#"
+        SELECT k.table_schema AS table_schema,
+               k.table_name AS table_name,
+               k.constraint_name AS name,
+               k.column_name AS `from`,
+               k.referenced_table_schema AS ref_schema,
+               k.referenced_table_name AS to_table,
+               k.referenced_column_name AS `to`,
+               r.update_rule AS on_update,
+               r.delete_rule AS on_delete
+        FROM information_schema.KEY_COLUMN_USAGE k
+        JOIN information_schema.REFERENTIAL_CONSTRAINTS r
+          ON r.constraint_schema = k.constraint_schema AND r.constraint_name = k.constraint_name
+         AND r.table_name = k.table_name
+        WHERE k.table_schema NOT IN ('mysql','information_schema','performance_schema','sys')
+          AND k.referenced_table_name IS NOT NULL{}
+        ORDER BY k.table_schema, k.table_name, k.constraint_name, k.ordinal_position
+        "#,
+        only("k.table_schema", "k.table_name")
+    )), &binds)
+    .fetch_all(pool)
+    .await?;
+    for r in fk_rows {
+        let Some(t) = tables.get(&r.try_get::<String, _>("table_schema")?, &r.try_get::<String, _>("table_name")?) else {
+            continue;
+        };
+        let name: Option<String> = r.try_get("name").ok();
+        let column: String = r.try_get("from").unwrap_or_default();
+        let to: String = r.try_get("to").unwrap_or_default();
+        // constraint names are unique per table, so a repeat continues the key
+        match t.foreign_keys.last_mut() {
+            Some(fk) if fk.name == name => {
+                fk.columns.push(column);
+                fk.ref_columns.push(to);
+            }
+            _ => t.foreign_keys.push(ForeignKeyDef {
+                name,
+                columns: vec![column],
                 ref_schema: r.try_get::<Option<String>, _>("ref_schema").unwrap_or(None),
-                to_table: r.try_get::<String, _>("to_table").unwrap_or_default(),
-                to: r.try_get::<String, _>("to").unwrap_or_default(),
-            })
+                to_table: r.try_get("to_table").unwrap_or_default(),
+                ref_columns: vec![to],
+                on_update: r.try_get("on_update").ok(),
+                on_delete: r.try_get("on_delete").ok(),
+            }),
+        }
+    }
+
+    let index_rows = bind_all(sqlx::query(&format!(
+        r#"
+        SELECT table_schema AS table_schema, table_name AS table_name,
+               index_name AS index_name, column_name AS column_name,
+               CAST(non_unique AS SIGNED) AS non_unique, index_type AS index_type
+        FROM information_schema.statistics
+        WHERE table_schema NOT IN ('mysql','information_schema','performance_schema','sys'){}
+        ORDER BY table_schema, table_name, index_name, seq_in_index
+        "#,
+        only("table_schema", "table_name")
+    )), &binds)
+    .fetch_all(pool)
+    .await?;
+    for r in index_rows {
+        let Some(t) = tables.get(&r.try_get::<String, _>("table_schema")?, &r.try_get::<String, _>("table_name")?) else {
+            continue;
+        };
+        let name: String = r.try_get("index_name").unwrap_or_default();
+        // functional key parts (8.0.13+) have no column name
+        let column = r
+            .try_get::<Option<String>, _>("column_name")
+            .unwrap_or(None)
+            .unwrap_or_else(|| "(expression)".into());
+        match t.indexes.last_mut() {
+            Some(ix) if ix.name == name => ix.columns.push(column),
+            _ => t.indexes.push(IndexDef {
+                primary: name == "PRIMARY",
+                name,
+                columns: vec![column],
+                unique: r.try_get::<i64, _>("non_unique").unwrap_or(1) == 0,
+                predicate: None,
+                method: r.try_get("index_type").ok(),
+            }),
+        }
+    }
+    // a MySQL UNIQUE constraint is its unique index
+    for t in &mut tables.list {
+        t.unique_constraints = t
+            .indexes
+            .iter()
+            .filter(|ix| ix.unique && !ix.primary)
+            .map(|ix| UniqueDef { name: ix.name.clone(), columns: ix.columns.clone() })
             .collect();
+    }
 
-        tables.push(TableDef {
-            schema,
-            name,
-            type_,
-            columns,
-            foreign_keys,
+    // check_constraints exists from MySQL 8.0.16 and MariaDB 10.2; older
+    // servers have no enforced checks to list
+    let check_rows = bind_all(sqlx::query(&format!(
+        r#"
+        SELECT tc.table_schema AS table_schema, tc.table_name AS table_name,
+               cc.constraint_name AS constraint_name, cc.check_clause AS check_clause
+        FROM information_schema.table_constraints tc
+        JOIN information_schema.check_constraints cc
+          ON cc.constraint_schema = tc.constraint_schema AND cc.constraint_name = tc.constraint_name
+        WHERE tc.table_schema NOT IN ('mysql','information_schema','performance_schema','sys')
+          AND tc.constraint_type = 'CHECK'{}
+        ORDER BY tc.table_schema, tc.table_name, cc.constraint_name
+        "#,
+        only("tc.table_schema", "tc.table_name")
+    )), &binds)
+    .fetch_all(pool)
+    .await;
+    let check_rows = match check_rows {
+        Ok(rows) => rows,
+        Err(e) if no_such_view(&e) => Vec::new(),
+        Err(e) => return Err(e.into()),
+    };
+    for r in check_rows {
+        let Some(t) = tables.get(&r.try_get::<String, _>("table_schema")?, &r.try_get::<String, _>("table_name")?) else {
+            continue;
+        };
+        t.checks.push(CheckDef {
+            name: r.try_get("constraint_name").ok(),
+            expression: r.try_get("check_clause").unwrap_or_default(),
         });
     }
 
-    let mut schemas: Vec<String> = tables.iter().map(|t| t.schema.clone()).collect();
-    schemas.sort();
-    schemas.dedup();
+    Ok(())
+}
+
+// `(?, ?), ...` over the listed tables for narrowing a MySQL catalog query,
+// with its binds; None reads the whole catalog. Past a few thousand tables
+// that is cheaper than the bind list, and rows outside the listing are
+// skipped anyway.
+fn mysql_pairs(tables: &Tables, narrow: bool) -> (Option<String>, Vec<String>) {
+    if !narrow || tables.list.len() > 5000 {
+        return (None, Vec::new());
+    }
+    let pairs = vec!["(?, ?)"; tables.list.len()].join(", ");
+    let binds = tables.list.iter().flat_map(|t| [t.schema.clone(), t.name.clone()]).collect();
+    (Some(pairs), binds)
+}
 
-    Ok(DatabaseSchema {
-        dialect: "mysql".into(),
-        schemas,
-        tables,
-    })
+fn bind_all<'q>(
+    mut q: sqlx::query::Query<'q, sqlx::MySql, sqlx::mysql::MySqlArguments>,
+    binds: &'q [String],
+) -> sqlx::query::Query<'q, sqlx::MySql, sqlx::mysql::MySqlArguments> {
+    for b in binds {
+        q = q.bind(b);
+    }
+    q
+}
+
+// SQL LIKE without escapes, ignoring case: `%` matches any run of
+// characters and `_` exactly one.
+fn like(pattern: &str, s: &str) -> bool {
+    let p: Vec<char> = pattern.to_lowercase().chars().collect();
+    let s: Vec<char> = s.to_lowercase().chars().collect();
+    let (mut pi, mut si) = (0, 0);
+    // the last `%` seen and where its match ends, to backtrack to
+    let mut star: Option<(usize, usize)> = None;
+    while si < s.len() {
+        // `%` first, or a literal `%` in the name would be taken as a match
+        if pi < p.len() && p[pi] == '%' {
+            star = Some((pi, si));
+            pi += 1;
+        } else if pi < p.len() && (p[pi] == '_' || p[pi] == s[si]) {
+            pi += 1;
+            si += 1;
+        } else if let Some((sp, ss)) = star {
+            star = Some((sp, ss + 1));
+            pi = sp + 1;
+            si = ss + 1;
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|&c| c == '%')
+}
+
+// pg_constraint.confupdtype / confdeltype codes.
+fn pg_fk_action(code: &str) -> String {
+    match code {
+        "r" => "RESTRICT",
+        "c" => "CASCADE",
+        "n" => "SET NULL",
+        "d" => "SET DEFAULT",
+        _ => "NO ACTION",
+    }
+    .to_string()
+}
+
+// Words, quoted strings/identifiers and single punctuation of a SQL text,
+// with comments dropped. Enough to find clauses in SQLite's stored DDL.
+fn sql_tokens(sql: &str) -> Vec<&str> {
+    let b = sql.as_bytes();
+    let word = |c: u8| c.is_ascii_alphanumeric() || c == b'_' || c == b'$' || c >= 0x80;
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < b.len() {
+        let start = i;
+        match b[i] {
+            q @ (b'\'' | b'"' | b'`') => {
+                i += 1;
+                while i < b.len() {
+                    i += 1;
+                    if b[i - 1] == q {
+                        if b.get(i) != Some(&q) {
+                            break;
+                        }
+                        i += 1;
+                    }
+                }
+            }
+            b'[' => {
+                while i < b.len() && b[i] != b']' {
+                    i += 1;
+                }
+                i = (i + 1).min(b.len());
+            }
+            b'-' if b.get(i + 1) == Some(&b'-') => {
+                while i < b.len() && b[i] != b'\n' {
+                    i += 1;
+                }
+                continue;
+            }
+            b'/' if b.get(i + 1) == Some(&b'*') => {
+                i = sql[i + 2..].find("*/").map_or(b.len(), |e| i + 2 + e + 2);
+                continue;
+            }
+            c if c.is_ascii_whitespace() => {
+                i += 1;
+                continue;
+            }
+            c if word(c) => {
+                while i < b.len() && word(b[i]) {
+                    i += 1;
+                }
+            }
+            _ => i += 1,
+        }
+        out.push(&sql[start..i]);
+    }
+    out
+}
+
+fn offset(sql: &str, token: &str) -> usize {
+    token.as_ptr() as usize - sql.as_ptr() as usize
+}
+
+fn unquote(ident: &str) -> String {
+    let b = ident.as_bytes();
+    match (b.first(), b.last()) {
+        (Some(b'['), Some(b']')) => ident[1..ident.len() - 1].to_string(),
+        (Some(&q @ (b'"' | b'`' | b'\'')), Some(&e)) if q == e && ident.len() > 1 => {
+            let q = q as char;
+            ident[1..ident.len() - 1].replace(&format!("{q}{q}"), &q.to_string())
+        }
+        _ => ident.to_string(),
+    }
+}
+
+/// CHECK constraints in a CREATE TABLE statement, column and table level.
+fn sqlite_checks(sql: &str) -> Vec<CheckDef> {
+    let toks = sql_tokens(sql);
+    let mut checks = Vec::new();
+    for (i, t) in toks.iter().enumerate() {
+        if !t.eq_ignore_ascii_case("check") || toks.get(i + 1) != Some(&"(") {
+            continue;
+        }
+        let mut depth = 0;
+        let close = toks[i + 1..].iter().position(|t| {
+            match *t {
+                "(" => depth += 1,
+                ")" => depth -= 1,
+                _ => {}
+            }
+            depth == 0
+        });
+        let Some(close) = close.map(|c| toks[i + 1 + c]) else { break };
+        let expression = sql[offset(sql, toks[i + 1]) + 1..offset(sql, close)].trim().to_string();
+        let name = (i >= 2 && toks[i - 2].eq_ignore_ascii_case("constraint")).then(|| unquote(toks[i - 1]));
+        checks.push(CheckDef { name, expression });
+    }
+    checks
+}
+
+/// WHERE clause of a CREATE INDEX statement.
+fn index_predicate(sql: &str) -> Option<String> {
+    let mut depth = 0;
+    for t in sql_tokens(sql) {
+        match t {
+            "(" => depth += 1,
+            ")" => depth -= 1,
+            _ if depth == 0 && t.eq_ignore_ascii_case("where") => {
+                let rest = &sql[offset(sql, t) + t.len()..];
+                return Some(rest.trim().trim_end_matches(';').trim_end().to_string());
+            }
+            _ => {}
+        }
+    }
+    None
 }
 
 #[cfg(test)]
@@ -412,4 +1269,201 @@ mod tests {
         assert_eq!(child_table.foreign_keys[0].to_table, "parent");
         Ok(())
     }
+
+    #[tokio::test]
+    async fn inspect_sqlite_indexes_and_constraints() -> anyhow::Result<()> {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await?;
+        pool.execute(
+            r#"
+            CREATE TABLE item (
+                id INTEGER PRIMARY KEY,
+                sku TEXT NOT NULL UNIQUE,
+                price REAL CHECK (price > 0), -- no ( CHECK ) in comments
+                qty INTEGER,
+                note TEXT DEFAULT 'check (x)',
+                CONSTRAINT "qty ok" CHECK (qty >= 0 AND (qty < 1000))
+            );
+            CREATE INDEX item_price ON item (price, qty DESC) WHERE qty IS NOT NULL;
+            "#,
+        )
+        .await?;
+
+        let schema = inspect_schema(&DynPool::Sqlite(pool.clone())).await?;
+        let item = schema.tables.iter().find(|t| t.name == "item").unwrap();
+        assert_eq!(
+            item.checks,
+            [
+                CheckDef { name: None, expression: "price > 0".into() },
+                CheckDef { name: Some("qty ok".into()), expression: "qty >= 0 AND (qty < 1000)".into() },
+            ]
+        );
+        assert_eq!(item.unique_constraints, [UniqueDef { name: "sqlite_autoindex_item_1".into(), columns: vec!["sku".into()] }]);
+        let partial = item.indexes.iter().find(|i| i.name == "item_price").unwrap();
+        assert_eq!(partial.columns, ["price", "qty"]);
+        assert!(!partial.unique);
+        assert_eq!(partial.predicate.as_deref(), Some("qty IS NOT NULL"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn inspect_sqlite_composite_foreign_keys() -> anyhow::Result<()> {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await?;
+        pool.execute(
+            r#"
+            CREATE TABLE region (country TEXT, code TEXT, PRIMARY KEY (country, code));
+            CREATE TABLE store (
+                id INTEGER PRIMARY KEY,
+                country TEXT,
+                code TEXT,
+                parent INTEGER REFERENCES store ON DELETE SET NULL,
+                FOREIGN KEY (country, code) REFERENCES region (country, code) ON UPDATE CASCADE
+            );
+            "#,
+        )
+        .await?;
+
+        let schema = inspect_schema(&DynPool::Sqlite(pool.clone())).await?;
+        let store = schema.tables.iter().find(|t| t.name == "store").unwrap();
+        let mut fks = store.foreign_keys.clone();
+        fks.sort_by(|a, b| a.to_table.cmp(&b.to_table));
+        assert_eq!(
+            fks,
+            [
+                ForeignKeyDef {
+                    name: None,
+                    columns: vec!["country".into(), "code".into()],
+                    ref_schema: None,
+                    to_table: "region".into(),
+                    ref_columns: vec!["country".into(), "code".into()],
+                    on_update: Some("CASCADE".into()),
+                    on_delete: Some("NO ACTION".into()),
+                },
+                ForeignKeyDef {
+                    name: None,
+                    columns: vec!["parent".into()],
+                    ref_schema: None,
+                    to_table: "store".into(),
+                    ref_columns: vec!["id".into()],
+                    on_update: Some("NO ACTION".into()),
+                    on_delete: Some("SET NULL".into()),
+                },
+            ]
+        );
+        Ok(())
+    }
+
+    /// Thousands of tables load in a fixed number of queries, well inside
+    /// the time budget even in debug builds. Timing depends on the machine,
+    /// so run it explicitly with `cargo test -- --ignored`.
+    #[tokio::test]
+    #[ignore = "benchmark"]
+    async fn inspect_sqlite_thousands_of_tables() -> anyhow::Result<()> {
+        const TABLES: usize = 3000;
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await?;
+        let mut ddl = String::from("BEGIN;");
+        for i in 0..TABLES {
+            ddl += &format!("CREATE TABLE t{i} (id INTEGER PRIMARY KEY, code TEXT UNIQUE, n INTEGER CHECK (n >= 0)");
+            if i > 0 {
+                ddl += &format!(", prev_id INTEGER REFERENCES t{} (id)", i - 1);
+            }
+            ddl += &format!("); CREATE INDEX t{i}_n ON t{i} (n);");
+        }
+        ddl += "COMMIT;";
+        pool.execute(ddl.as_str()).await?;
+
+        let started = std::time::Instant::now();
+        let schema = inspect_schema(&DynPool::Sqlite(pool.clone())).await?;
+        let elapsed = started.elapsed();
+
+        assert_eq!(schema.tables.len(), TABLES);
+        let t = schema.tables.iter().find(|t| t.name == "t1234").unwrap();
+        assert_eq!(t.columns.len(), 4);
+        assert_eq!(t.foreign_keys[0].to_table, "t1233");
+        assert_eq!(t.indexes.len(), 2);
+        assert_eq!(t.unique_constraints[0].columns, ["code"]);
+        assert_eq!(t.checks[0].expression, "n >= 0");
+        assert!(elapsed < std::time::Duration::from_secs(5), "introspection took {elapsed:?}");
+        Ok(())
+    }
+
+    #[test]
+    fn like_patterns_ignore_case() {
+        assert!(like("user%", "Users"));
+        assert!(like("%_log", "audit_log"));
+        assert!(like("a%b%c", "aXbYbZc"));
+        assert!(like("t_", "t1"));
+        assert!(!like("t_", "t12"));
+        assert!(!like("%log", "logs"));
+        assert!(like("%", ""));
+        assert!(like("%a", "%ba"));
+
+        let f = ObjectFilter { schemas: vec!["public".into()], name_pattern: Some("ord%".into()), ..Default::default() };
+        assert!(f.keeps("public", "orders"));
+        assert!(!f.keeps("audit", "orders"));
+        assert!(!f.keeps("public", "users"));
+    }
+
+    #[tokio::test]
+    async fn list_and_describe_sqlite_on_demand() -> anyhow::Result<()> {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await?;
+        pool.execute(
+            r#"
+            CREATE TABLE orders (id INTEGER PRIMARY KEY, total INTEGER CHECK (total >= 0));
+            CREATE TABLE order_items (id INTEGER PRIMARY KEY, order_id INTEGER REFERENCES orders (id));
+            CREATE TABLE users (id INTEGER PRIMARY KEY, email TEXT UNIQUE);
+            CREATE TABLE sessions (id INTEGER PRIMARY KEY, user_id INTEGER REFERENCES users);
+            CREATE VIEW big_orders AS SELECT * FROM orders WHERE total > 100;
+            "#,
+        )
+        .await?;
+        let pool = DynPool::Sqlite(pool);
+
+        let all = list_objects(&pool, &ObjectFilter::default()).await?;
+        assert_eq!(all.schemas, ["main"]);
+        let kinds: Vec<_> = all.objects.iter().map(|o| (o.name.as_str(), o.kind)).collect();
+        assert_eq!(
+            kinds,
+            [
+                ("big_orders", ObjectKind::View),
+                ("order_items", ObjectKind::Table),
+                ("orders", ObjectKind::Table),
+                ("sessions", ObjectKind::Table),
+                ("users", ObjectKind::Table),
+            ]
+        );
+
+        let filter = ObjectFilter { name_pattern: Some("ORDER%".into()), ..Default::default() };
+        let schema = inspect_filtered(&pool, &filter).await?;
+        let names: Vec<_> = schema.tables.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["order_items", "orders"]);
+        assert_eq!(schema.tables[0].foreign_keys[0].to_table, "orders");
+        assert_eq!(schema.tables[1].checks[0].expression, "total >= 0");
+
+        let refs = [
+            ObjectRef { schema: None, name: "users".into() },
+            ObjectRef { schema: Some("main".into()), name: "missing".into() },
+        ];
+        let tables = describe_tables(&pool, &refs).await?;
+        assert_eq!(tables.len(), 1);
+        assert_eq!(tables[0].columns.len(), 2);
+        assert_eq!(tables[0].unique_constraints[0].columns, ["email"]);
+
+        // the parent of a bare `REFERENCES` is looked up even when not described
+        let tables = describe_tables(&pool, &[ObjectRef { schema: None, name: "sessions".into() }]).await?;
+        assert_eq!(tables[0].foreign_keys[0].to_table, "users");
+        assert_eq!(tables[0].foreign_keys[0].ref_columns, ["id"]);
+        Ok(())
+    }
 }
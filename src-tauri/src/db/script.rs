@@ -0,0 +1,128 @@
+use std::time::Instant;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::cancel::{self, QueryTag};
+use super::statement::split_statements;
+use super::{DynPool, QueryResult};
+
+/// How a multi-statement script is run.
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub struct ScriptOptions {
+    /// Wrap the script in one transaction: it is committed only if every
+    /// statement succeeds and rolled back at the first error. Note that MySQL
+    /// commits implicitly on DDL.
+    #[serde(default)]
+    pub transaction: bool,
+    /// Stop at the first failing statement. Always on inside a transaction.
+    #[serde(default = "default_stop_on_error")]
+    pub stop_on_error: bool,
+}
+
+fn default_stop_on_error() -> bool {
+    true
+}
+
+impl Default for ScriptOptions {
+    fn default() -> Self {
+        Self { transaction: false, stop_on_error: true }
+    }
+}
+
+/// Outcome of one statement; exactly one of `result` / `error` is set.
+#[derive(Serialize)]
+pub struct StatementOutcome {
+    /// Zero-based position in the script.
+    pub index: usize,
+    pub sql: String,
+    pub result: Option<QueryResult>,
+    pub error: Option<String>,
+}
+
+/// Outcomes of the statements that ran, in script order.
+#[derive(Serialize)]
+pub struct ScriptResult {
+    pub statements: Vec<StatementOutcome>,
+    /// Number of statements in the script; more than `statements.len()` when
+    /// execution stopped early.
+    pub total: usize,
+    /// Whether the transaction was committed; None outside transaction mode.
+    pub committed: Option<bool>,
+    pub elapsed_ms: f64,
+}
+
+/// Split `sql` and run each statement in order on a single connection,
+/// keeping at most `limit` rows per result set.
+pub async fn run_script(
+    pool: &DynPool,
+    sql: &str,
+    opts: ScriptOptions,
+    limit: u32,
+    tag: Option<&QueryTag<'_>>,
+) -> Result<ScriptResult> {
+    let started = Instant::now();
+    let dialect = pool.dialect();
+    let stmts = split_statements(sql, dialect);
+
+    let mut conn = pool.acquire().await?;
+    // registered before BEGIN, so a refused query id leaves no transaction
+    // open on the pooled connection
+    let tracked = cancel::track(tag, pool, &mut conn).await?;
+    if opts.transaction {
+        if let Err(e) = conn.begin().await {
+            return cancel::finish(tracked, &mut conn, Err(e)).await;
+        }
+    }
+
+    let mut outcomes = Vec::with_capacity(stmts.len());
+    let mut failed = false;
+    for (index, stmt) in stmts.iter().enumerate() {
+        let cancelled = || tracked.as_ref().is_some_and(|t| t.is_cancelled());
+        let res = if cancelled() {
+            Err(anyhow::anyhow!("query cancelled"))
+        } else {
+            conn.run(stmt, None, limit as usize, None).await
+        };
+        let (result, error) = match res {
+            Ok(r) => (Some(r), None),
+            Err(_) if cancelled() => (None, Some("query cancelled".to_string())),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        failed |= error.is_some();
+        let stop = error.is_some() && (opts.stop_on_error || opts.transaction || cancelled());
+        outcomes.push(StatementOutcome { index, sql: stmt.clone(), result, error });
+        if stop {
+            break;
+        }
+    }
+    // drops the SQLite progress handler so COMMIT/ROLLBACK cannot be interrupted
+    if let Err(e) = cancel::finish(tracked, &mut conn, Ok(())).await {
+        if opts.transaction {
+            let _ = conn.execute("ROLLBACK").await;
+        }
+        return Err(e);
+    }
+
+    let committed = if opts.transaction {
+        if failed {
+            conn.execute("ROLLBACK").await?;
+            Some(false)
+        } else if let Err(e) = conn.execute("COMMIT").await {
+            // a failed COMMIT can leave the transaction open (SQLite when busy)
+            let _ = conn.execute("ROLLBACK").await;
+            anyhow::bail!("commit failed: {}", e);
+        } else {
+            Some(true)
+        }
+    } else {
+        None
+    };
+
+    Ok(ScriptResult {
+        statements: outcomes,
+        total: stmts.len(),
+        committed,
+        elapsed_ms: started.elapsed().as_secs_f64() * 1000.0,
+    })
+}
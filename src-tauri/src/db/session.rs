@@ -0,0 +1,310 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use sea_query_binder::SqlxValues;
+use serde::Serialize;
+
+use super::cancel::{self, QueryTag};
+use super::statement::{is_transaction_control, StatementKind};
+use super::{gen_id, pool_of, schema_may_change, BatchSink, DynConn, DynPool, QueryResult, Registry};
+
+/// Open transaction sessions keyed by session id. Each session is locked while
+/// a statement runs on it, so statements on one session never interleave.
+pub type Sessions = Arc<Mutex<HashMap<String, Arc<Session>>>>;
+
+/// A transaction pinned to one connection checked out of the pool. The
+/// connection goes back to the pool when the session ends.
+pub struct Session {
+    conn_id: String,
+    pinned: tokio::sync::Mutex<Pinned>,
+}
+
+struct Pinned {
+    pool: DynPool,
+    conn: DynConn,
+    uncommitted: bool,
+    /// DDL ran; other connections only see it after COMMIT.
+    ddl: bool,
+}
+
+/// Session state shown in the UI.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct TransactionState {
+    pub session_id: String,
+    pub conn_id: String,
+    /// True once a statement that writes (DML or DDL) has succeeded.
+    pub uncommitted: bool,
+}
+
+/// Check out a dedicated connection and start a transaction on it. Sessions
+/// hold their connection until they end, so one pooled connection is always
+/// left for everything else; past that, begin fails instead of later
+/// statements waiting on the pool.
+pub async fn begin(reg: &Registry, conn_id: &str) -> Result<TransactionState> {
+    let pool = pool_of(reg, conn_id).await?;
+    let limit = (pool.max_connections() as usize).saturating_sub(1).max(1);
+    let too_many = || anyhow::anyhow!("at most {limit} transactions can be open on a connection; commit or roll back one first");
+    if open_on(reg, conn_id) >= limit {
+        return Err(too_many());
+    }
+    let mut conn = pool.acquire().await?;
+    conn.begin().await?;
+
+    let session_id = format!("tx-{}", gen_id());
+    let session = Arc::new(Session {
+        conn_id: conn_id.to_string(),
+        pinned: tokio::sync::Mutex::new(Pinned { pool, conn, uncommitted: false, ddl: false }),
+    });
+    // a concurrent begin may have taken the last slot meanwhile
+    let added = {
+        let mut sessions = reg.sessions.lock().unwrap();
+        let room = sessions.values().filter(|s| s.conn_id == conn_id).count() < limit;
+        if room {
+            sessions.insert(session_id.clone(), session.clone());
+        }
+        room
+    };
+    if !added {
+        let _ = session.pinned.lock().await.conn.execute("ROLLBACK").await;
+        return Err(too_many());
+    }
+    Ok(TransactionState { session_id, conn_id: conn_id.to_string(), uncommitted: false })
+}
+
+/// Run one statement inside the session's transaction. The session must
+/// belong to `conn_id`, whose dialect the statement was built for.
+#[allow(clippy::too_many_arguments)]
+pub async fn run(
+    reg: &Registry,
+    conn_id: &str,
+    session_id: &str,
+    sql: &str,
+    values: Option<SqlxValues>,
+    limit: u32,
+    sink: Option<&mut BatchSink<'_>>,
+    query_id: Option<&str>,
+) -> Result<QueryResult> {
+    if is_transaction_control(sql) {
+        anyhow::bail!("use commit or rollback to end the transaction");
+    }
+    let session = get(reg, session_id)?;
+    if session.conn_id != conn_id {
+        anyhow::bail!("transaction belongs to another connection");
+    }
+    let mut s = session.pinned.lock().await;
+    let s = &mut *s;
+
+    let tag = query_id.map(|query_id| QueryTag { running: &reg.running, conn_id: &session.conn_id, query_id });
+    let tracked = cancel::track(tag.as_ref(), &s.pool, &mut s.conn).await?;
+    let res = match cancel::ensure_running(tracked.as_ref()) {
+        Ok(()) => s.conn.run(sql, values, limit as usize, sink).await,
+        Err(e) => Err(e),
+    };
+    let res = cancel::finish(tracked, &mut s.conn, res).await;
+
+    if let Ok(r) = &res {
+        schema_may_change(reg, &session.conn_id, r.kind);
+        s.ddl |= r.kind == StatementKind::Ddl;
+        if matches!(
+            r.kind,
+            StatementKind::Insert | StatementKind::Update | StatementKind::Delete | StatementKind::Ddl
+        ) {
+            s.uncommitted = true;
+        }
+    }
+    res
+}
+
+/// Current state of a session.
+pub async fn state(reg: &Registry, session_id: &str) -> Result<TransactionState> {
+    let session = get(reg, session_id)?;
+    let uncommitted = session.pinned.lock().await.uncommitted;
+    Ok(TransactionState {
+        session_id: session_id.to_string(),
+        conn_id: session.conn_id.clone(),
+        uncommitted,
+    })
+}
+
+/// Commit and end the session. If the commit fails the transaction is rolled
+/// back so the connection does not return to the pool mid-transaction.
+pub async fn commit(reg: &Registry, session_id: &str) -> Result<()> {
+    let session = take(reg, session_id)?;
+    let mut s = session.pinned.lock().await;
+    if let Err(e) = s.conn.execute("COMMIT").await {
+        let _ = s.conn.execute("ROLLBACK").await;
+        return Err(e);
+    }
+    // the schema may have been cached from another connection while the
+    // DDL was still uncommitted
+    if s.ddl {
+        schema_may_change(reg, &session.conn_id, StatementKind::Ddl);
+    }
+    Ok(())
+}
+
+/// Roll back and end the session.
+pub async fn rollback(reg: &Registry, session_id: &str) -> Result<()> {
+    let session = take(reg, session_id)?;
+    let mut s = session.pinned.lock().await;
+    s.conn.execute("ROLLBACK").await
+}
+
+/// Roll back every session on a connection (used when it is closed). Each
+/// session ends even if its ROLLBACK fails; the failures are reported together.
+pub async fn rollback_all(reg: &Registry, conn_id: &str) -> Result<()> {
+    let ids: Vec<String> = reg
+        .sessions
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(_, s)| s.conn_id == conn_id)
+        .map(|(id, _)| id.clone())
+        .collect();
+    let mut errors = Vec::new();
+    for id in ids {
+        if let Err(e) = rollback(reg, &id).await {
+            errors.push(format!("{id}: {e}"));
+        }
+    }
+    if !errors.is_empty() {
+        anyhow::bail!("could not roll back {}", errors.join("; "));
+    }
+    Ok(())
+}
+
+fn open_on(reg: &Registry, conn_id: &str) -> usize {
+    reg.sessions.lock().unwrap().values().filter(|s| s.conn_id == conn_id).count()
+}
+
+fn get(reg: &Registry, session_id: &str) -> Result<Arc<Session>> {
+    reg.sessions
+        .lock()
+        .unwrap()
+        .get(session_id)
+        .cloned()
+        .ok_or_else(|| anyhow::anyhow!("transaction not found"))
+}
+
+fn take(reg: &Registry, session_id: &str) -> Result<Arc<Session>> {
+    reg.sessions
+        .lock()
+        .unwrap()
+        .remove(session_id)
+        .ok_or_else(|| anyhow::anyhow!("transaction not found"))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::*;
+    use crate::db::{cached_schema, close_connection, execute_sql, open_connection, Driver, TempDb};
+
+    /// Statements routed through a session share its transaction: other
+    /// connections do not see them until commit, and closing the connection
+    /// rolls back what is still open.
+    #[tokio::test]
+    async fn transaction_session_pins_one_connection_sqlite() -> anyhow::Result<()> {
+        let db = TempDb::open("tx").await?;
+        execute_sql(&db.reg, &db.conn_id, "CREATE TABLE t (v INTEGER)", 10, None, None).await?;
+        async fn count(reg: &Registry, conn_id: &str, session_id: Option<&str>) -> anyhow::Result<Value> {
+            let r = execute_sql(reg, conn_id, "SELECT count(*) FROM t", 10, None, session_id).await?;
+            Ok(r.rows[0][0].clone())
+        }
+
+        let tx = begin(&db.reg, &db.conn_id).await?;
+        assert!(!tx.uncommitted);
+        execute_sql(&db.reg, &db.conn_id, "INSERT INTO t VALUES (1)", 10, None, Some(&tx.session_id)).await?;
+        assert!(state(&db.reg, &tx.session_id).await?.uncommitted);
+        assert_eq!(count(&db.reg, &db.conn_id, Some(&tx.session_id)).await?, serde_json::json!(1));
+        assert_eq!(count(&db.reg, &db.conn_id, None).await?, serde_json::json!(0));
+        let err = execute_sql(&db.reg, &db.conn_id, "COMMIT", 10, None, Some(&tx.session_id)).await;
+        assert!(err.is_err());
+        let other = open_connection(&db.reg, Driver::Sqlite, &db.url).await?;
+        let err = execute_sql(&db.reg, &other, "INSERT INTO t VALUES (9)", 10, None, Some(&tx.session_id)).await;
+        assert_eq!(err.err().unwrap().to_string(), "transaction belongs to another connection");
+        close_connection(&db.reg, &other).await?;
+        rollback(&db.reg, &tx.session_id).await?;
+        assert!(state(&db.reg, &tx.session_id).await.is_err());
+        assert_eq!(count(&db.reg, &db.conn_id, None).await?, serde_json::json!(0));
+
+        let tx = begin(&db.reg, &db.conn_id).await?;
+        execute_sql(&db.reg, &db.conn_id, "INSERT INTO t VALUES (2)", 10, None, Some(&tx.session_id)).await?;
+        commit(&db.reg, &tx.session_id).await?;
+        assert_eq!(count(&db.reg, &db.conn_id, None).await?, serde_json::json!(1));
+
+        // DDL stays invisible elsewhere until commit, so a schema cached in
+        // the meantime is dropped again by the commit
+        let tx = begin(&db.reg, &db.conn_id).await?;
+        execute_sql(&db.reg, &db.conn_id, "CREATE TABLE later (id INTEGER)", 10, None, Some(&tx.session_id)).await?;
+        assert!(cached_schema(&db.reg, &db.conn_id).await?.tables.iter().all(|t| t.name != "later"));
+        commit(&db.reg, &tx.session_id).await?;
+        assert!(cached_schema(&db.reg, &db.conn_id).await?.tables.iter().any(|t| t.name == "later"));
+
+        let tx = begin(&db.reg, &db.conn_id).await?;
+        execute_sql(&db.reg, &db.conn_id, "INSERT INTO t VALUES (3)", 10, None, Some(&tx.session_id)).await?;
+        close_connection(&db.reg, &db.conn_id).await?;
+        assert!(db.reg.sessions.lock().unwrap().is_empty());
+
+        let reopened = open_connection(&db.reg, Driver::Sqlite, &db.url).await?;
+        let r = execute_sql(&db.reg, &reopened, "SELECT count(*) FROM t", 10, None, None).await?;
+        assert_eq!(r.rows[0][0], serde_json::json!(1));
+        Ok(())
+    }
+
+    /// Sessions never take the last pooled connection, so begin refuses
+    /// rather than leaving other statements waiting on the pool.
+    #[tokio::test]
+    async fn begin_leaves_a_connection_free_sqlite() -> anyhow::Result<()> {
+        let db = TempDb::open("limit").await?;
+        let mut open = Vec::new();
+        for _ in 0..4 {
+            open.push(begin(&db.reg, &db.conn_id).await?);
+        }
+        let err = tokio::time::timeout(std::time::Duration::from_secs(5), begin(&db.reg, &db.conn_id)).await?;
+        assert!(err.err().unwrap().to_string().starts_with("at most 4 transactions"));
+        let r = execute_sql(&db.reg, &db.conn_id, "SELECT 1", 10, None, None).await?;
+        assert_eq!(r.rows[0][0], serde_json::json!(1));
+
+        rollback(&db.reg, &open[0].session_id).await?;
+        begin(&db.reg, &db.conn_id).await?;
+        close_connection(&db.reg, &db.conn_id).await?;
+        Ok(())
+    }
+
+    /// A failing ROLLBACK does not keep the connection or its other
+    /// sessions alive.
+    #[tokio::test]
+    async fn close_connection_is_best_effort_sqlite() -> anyhow::Result<()> {
+        let db = TempDb::open("close").await?;
+        execute_sql(&db.reg, &db.conn_id, "CREATE TABLE t (v INTEGER)", 10, None, None).await?;
+        execute_sql(&db.reg, &db.conn_id, "CREATE TABLE doomed (v INTEGER)", 10, None, None).await?;
+        execute_sql(
+            &db.reg,
+            &db.conn_id,
+            "CREATE TRIGGER abort BEFORE INSERT ON doomed BEGIN SELECT RAISE(ROLLBACK, 'no'); END",
+            10,
+            None,
+            None,
+        )
+        .await?;
+
+        // RAISE(ROLLBACK) ends the transaction, so the session's ROLLBACK fails
+        let broken = begin(&db.reg, &db.conn_id).await?;
+        let err = execute_sql(&db.reg, &db.conn_id, "INSERT INTO doomed VALUES (1)", 10, None, Some(&broken.session_id)).await;
+        assert!(err.is_err());
+        let open = begin(&db.reg, &db.conn_id).await?;
+        execute_sql(&db.reg, &db.conn_id, "INSERT INTO t VALUES (1)", 10, None, Some(&open.session_id)).await?;
+
+        assert!(close_connection(&db.reg, &db.conn_id).await.is_err());
+        assert!(db.reg.sessions.lock().unwrap().is_empty());
+        assert!(execute_sql(&db.reg, &db.conn_id, "SELECT 1", 10, None, None).await.is_err());
+
+        let reopened = open_connection(&db.reg, Driver::Sqlite, &db.url).await?;
+        let r = execute_sql(&db.reg, &reopened, "SELECT count(*) FROM t", 10, None, None).await?;
+        assert_eq!(r.rows[0][0], serde_json::json!(0));
+        Ok(())
+    }
+}
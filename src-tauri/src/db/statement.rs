@@ -0,0 +1,416 @@
+use serde::Serialize;
+
+use super::Dialect;
+
+/// Kind of a SQL statement, judged from its leading keyword.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum StatementKind {
+    /// Returns rows: SELECT, VALUES, SHOW, EXPLAIN, PRAGMA, ...
+    Query,
+    Insert,
+    Update,
+    Delete,
+    /// Schema changes: CREATE, ALTER, DROP, TRUNCATE, ...
+    Ddl,
+    /// Anything else (SET, BEGIN, VACUUM, GRANT, ...).
+    Other,
+}
+
+impl StatementKind {
+    /// Whether `rows_affected` is meaningful for this statement.
+    pub fn modifies_rows(self) -> bool {
+        !matches!(self, StatementKind::Query)
+    }
+}
+
+/// Classify a statement. A leading `WITH` is looked through, so
+/// `WITH x AS (...) DELETE ...` is a delete rather than a query.
+pub fn statement_kind(sql: &str) -> StatementKind {
+    let trimmed = skip_comments(sql);
+    if trimmed.starts_with('(') {
+        return StatementKind::Query;
+    }
+
+    let words = top_level_words(trimmed);
+    let mut iter = words.iter().map(String::as_str);
+    let main = match iter.next() {
+        Some("WITH") => iter
+            .find(|w| matches!(*w, "SELECT" | "INSERT" | "UPDATE" | "DELETE" | "MERGE" | "VALUES"))
+            .unwrap_or("SELECT"),
+        Some(w) => w,
+        None => return StatementKind::Other,
+    };
+
+    match main {
+        "SELECT" | "VALUES" | "TABLE" | "SHOW" | "EXPLAIN" | "DESCRIBE" | "DESC" | "PRAGMA" => {
+            StatementKind::Query
+        }
+        "INSERT" | "REPLACE" => StatementKind::Insert,
+        "UPDATE" => StatementKind::Update,
+        "DELETE" => StatementKind::Delete,
+        "CREATE" | "ALTER" | "DROP" | "TRUNCATE" | "RENAME" | "COMMENT" => StatementKind::Ddl,
+        _ => StatementKind::Other,
+    }
+}
+
+/// Whether the statement starts or ends a transaction (`BEGIN`, `START
+/// TRANSACTION`, `COMMIT`, `END`, `ROLLBACK`). `ROLLBACK TO SAVEPOINT` does not.
+pub fn is_transaction_control(sql: &str) -> bool {
+    let words = top_level_words(skip_comments(sql));
+    match words.first().map(String::as_str) {
+        Some("BEGIN" | "COMMIT" | "END") => true,
+        Some("START") => words.get(1).map(String::as_str) == Some("TRANSACTION"),
+        Some("ROLLBACK") => words.iter().all(|w| w != "TO"),
+        _ => false,
+    }
+}
+
+/// Split a script into statements on top-level `;`, following the dialect's
+/// quoting and comment rules. Semicolons inside trigger bodies (SQLite
+/// `BEGIN ... END`, Postgres `BEGIN ATOMIC ... END`) and Postgres dollar-quoted
+/// bodies do not split; MySQL scripts may switch terminators with `DELIMITER`.
+/// Empty and comment-only statements are dropped.
+pub fn split_statements(sql: &str, dialect: Dialect) -> Vec<String> {
+    let mysql = matches!(dialect, Dialect::MySql);
+    let pg = matches!(dialect, Dialect::Postgres);
+    let sqlite = matches!(dialect, Dialect::Sqlite);
+
+    let b = sql.as_bytes();
+    let mut out = Vec::new();
+    let mut delimiter = ";".to_string();
+    let mut start = 0;
+    let mut has_code = false;
+    // upper-cased words of the current statement, outside quotes and comments
+    let mut words: Vec<String> = Vec::new();
+    let mut depth = 0usize;
+    let mut i = 0;
+
+    while i < b.len() {
+        let c = b[i];
+        let next = b.get(i + 1).copied();
+
+        if mysql && !has_code && starts_with_word(&b[i..], b"DELIMITER") {
+            let end = sql[i..].find('\n').map(|n| i + n).unwrap_or(b.len());
+            let d = sql[i + "DELIMITER".len()..end].trim();
+            if !d.is_empty() {
+                delimiter = d.to_string();
+            }
+            i = end;
+            start = i;
+            continue;
+        }
+
+        if is_word_byte(c) {
+            let end = i + b[i..].iter().take_while(|&&n| is_word_byte(n)).count();
+            // E'...' escape strings are lexed as strings below
+            if !(pg && end == i + 1 && (c == b'E' || c == b'e') && next == Some(b'\'')) {
+                has_code = true;
+                let word = sql[i..end].to_ascii_uppercase();
+                match word.as_str() {
+                    "BEGIN" if sqlite && is_create_trigger(&words) => depth += 1,
+                    "ATOMIC" if pg && words.last().map(String::as_str) == Some("BEGIN") => depth += 1,
+                    "CASE" if depth > 0 => depth += 1,
+                    "END" if depth > 0 => depth -= 1,
+                    _ => {}
+                }
+                words.push(word);
+                i = end;
+                continue;
+            }
+        }
+
+        if depth == 0 && b[i..].starts_with(delimiter.as_bytes()) {
+            push_statement(&mut out, &sql[start..i], has_code);
+            i += delimiter.len();
+            start = i;
+            has_code = false;
+            words.clear();
+            continue;
+        }
+
+        match c {
+            b'-' if next == Some(b'-')
+                && (!mysql || b.get(i + 2).is_none_or(|n| n.is_ascii_whitespace())) =>
+            {
+                i = sql[i..].find('\n').map(|n| i + n + 1).unwrap_or(b.len());
+            }
+            b'#' if mysql => {
+                i = sql[i..].find('\n').map(|n| i + n + 1).unwrap_or(b.len());
+            }
+            b'/' if next == Some(b'*') => {
+                i = skip_block_comment(b, i, pg);
+            }
+            b'\'' => {
+                has_code = true;
+                let escapes = mysql || (pg && is_escape_prefix(b, i));
+                i = skip_quoted(b, i, b'\'', escapes);
+            }
+            b'"' => {
+                has_code = true;
+                i = skip_quoted(b, i, b'"', mysql);
+            }
+            b'`' if !pg => {
+                has_code = true;
+                i = skip_quoted(b, i, b'`', false);
+            }
+            b'[' if sqlite => {
+                has_code = true;
+                i = sql[i..].find(']').map(|n| i + n + 1).unwrap_or(b.len());
+            }
+            b'$' if pg && (i == 0 || !is_word_byte(b[i - 1])) => {
+                has_code = true;
+                i = match dollar_tag(&sql[i..]) {
+                    Some(tag) => {
+                        let body = i + tag.len();
+                        sql[body..].find(tag).map(|n| body + n + tag.len()).unwrap_or(b.len())
+                    }
+                    None => i + 1,
+                };
+            }
+            _ => {
+                if !c.is_ascii_whitespace() {
+                    has_code = true;
+                }
+                i += 1;
+            }
+        }
+    }
+    push_statement(&mut out, &sql[start..], has_code);
+    out
+}
+
+fn push_statement(out: &mut Vec<String>, chunk: &str, has_code: bool) {
+    if has_code {
+        out.push(chunk.trim().to_string());
+    }
+}
+
+// Postgres `E'...'` strings honour backslash escapes.
+fn is_escape_prefix(b: &[u8], quote: usize) -> bool {
+    quote > 0
+        && b[quote - 1].eq_ignore_ascii_case(&b'E')
+        && (quote < 2 || !is_word_byte(b[quote - 2]))
+}
+
+fn is_word_byte(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || c == b'_' || c >= 0x80
+}
+
+fn starts_with_word(b: &[u8], word: &[u8]) -> bool {
+    b.len() >= word.len()
+        && b[..word.len()].eq_ignore_ascii_case(word)
+        && b.get(word.len()).is_none_or(|c| !is_word_byte(*c))
+}
+
+// CREATE [TEMP] TRIGGER ...; the body's BEGIN ... END holds inner semicolons.
+fn is_create_trigger(words: &[String]) -> bool {
+    words.first().map(String::as_str) == Some("CREATE")
+        && words.iter().take(3).any(|w| w == "TRIGGER")
+}
+
+// Index just past the quote closing the one at `open`; a doubled quote is an
+// escaped quote, as is a backslash-escaped one when `backslash` is set.
+fn skip_quoted(b: &[u8], open: usize, quote: u8, backslash: bool) -> usize {
+    let mut i = open + 1;
+    while i < b.len() {
+        match b[i] {
+            b'\\' if backslash => i += 2,
+            c if c == quote => {
+                if b.get(i + 1) == Some(&quote) {
+                    i += 2;
+                } else {
+                    return i + 1;
+                }
+            }
+            _ => i += 1,
+        }
+    }
+    b.len()
+}
+
+// Index just past a block comment starting at `open`; Postgres comments nest.
+fn skip_block_comment(b: &[u8], open: usize, nested: bool) -> usize {
+    let mut depth = 0;
+    let mut i = open;
+    while i + 1 < b.len() {
+        if b[i] == b'/' && b[i + 1] == b'*' {
+            if depth == 0 || nested {
+                depth += 1;
+            }
+            i += 2;
+        } else if b[i] == b'*' && b[i + 1] == b'/' {
+            depth -= 1;
+            i += 2;
+            if depth == 0 {
+                return i;
+            }
+        } else {
+            i += 1;
+        }
+    }
+    b.len()
+}
+
+// `$tag$` or `$$` at the start of `s`; `$1` style parameters are not tags.
+fn dollar_tag(s: &str) -> Option<&str> {
+    let b = s.as_bytes();
+    let len = b[1..].iter().take_while(|&&c| is_word_byte(c)).count();
+    if b.get(1 + len) != Some(&b'$') || b.get(1).is_some_and(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    Some(&s[..len + 2])
+}
+
+// Skip leading whitespace and comments.
+fn skip_comments(mut sql: &str) -> &str {
+    loop {
+        sql = sql.trim_start();
+        if let Some(rest) = sql.strip_prefix("--") {
+            sql = rest.find('\n').map(|i| &rest[i + 1..]).unwrap_or("");
+        } else if let Some(rest) = sql.strip_prefix("/*") {
+            sql = rest.find("*/").map(|i| &rest[i + 2..]).unwrap_or("");
+        } else {
+            return sql;
+        }
+    }
+}
+
+// Upper-cased words outside parentheses, quotes and comments.
+fn top_level_words(sql: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut depth = 0usize;
+    let mut chars = sql.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c.is_ascii_alphanumeric() || c == '_' {
+            if depth == 0 {
+                word.push(c.to_ascii_uppercase());
+            }
+            continue;
+        }
+        if !word.is_empty() {
+            words.push(std::mem::take(&mut word));
+        }
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            '\'' | '"' | '`' => {
+                // doubled quotes inside are consumed as two adjacent literals
+                for n in chars.by_ref() {
+                    if n == c {
+                        break;
+                    }
+                }
+            }
+            '-' if chars.peek() == Some(&'-') => {
+                for n in chars.by_ref() {
+                    if n == '\n' {
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = ' ';
+                for n in chars.by_ref() {
+                    if prev == '*' && n == '/' {
+                        break;
+                    }
+                    prev = n;
+                }
+            }
+            _ => {}
+        }
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn statement_kind_from_leading_keyword() {
+        assert_eq!(statement_kind("select 1"), StatementKind::Query);
+        assert_eq!(statement_kind("  -- note\n/* x */ INSERT INTO t VALUES (1)"), StatementKind::Insert);
+        assert_eq!(statement_kind("update t set a = 'select'"), StatementKind::Update);
+        assert_eq!(statement_kind("(SELECT 1) UNION (SELECT 2)"), StatementKind::Query);
+        assert_eq!(statement_kind("create table t (id int)"), StatementKind::Ddl);
+        assert_eq!(
+            statement_kind("WITH old AS (SELECT id FROM t WHERE x < 1) DELETE FROM t WHERE id IN (SELECT id FROM old)"),
+            StatementKind::Delete
+        );
+        assert_eq!(
+            statement_kind("with recursive c(x) as (select 1 union all select x + 1 from c) select * from c"),
+            StatementKind::Query
+        );
+        assert_eq!(statement_kind("BEGIN"), StatementKind::Other);
+        assert_eq!(statement_kind(""), StatementKind::Other);
+    }
+
+    #[test]
+    fn transaction_control_statements() {
+        assert!(is_transaction_control("commit"));
+        assert!(is_transaction_control("/* x */ ROLLBACK WORK"));
+        assert!(is_transaction_control("start transaction read only"));
+        assert!(!is_transaction_control("ROLLBACK TO SAVEPOINT s1"));
+        assert!(!is_transaction_control("SAVEPOINT s1"));
+        assert!(!is_transaction_control("select 'commit'"));
+    }
+
+    #[test]
+    fn split_statements_respects_quotes_and_comments() {
+        let sql = "insert into t values ('a;b', \"c;d\"); -- trailing; comment\n\
+                   /* block; */ select 1;;  \n-- only a comment\n";
+        assert_eq!(
+            split_statements(sql, Dialect::Sqlite),
+            vec!["insert into t values ('a;b', \"c;d\")", "-- trailing; comment\n/* block; */ select 1"]
+        );
+        assert_eq!(split_statements("select 'it''s; fine' ; select [a;b] from t", Dialect::Sqlite).len(), 2);
+    }
+
+    #[test]
+    fn split_statements_keeps_sqlite_trigger_bodies() {
+        let sql = "CREATE TRIGGER trg AFTER INSERT ON t BEGIN \
+                     UPDATE t SET n = CASE WHEN n > 0 THEN n END; \
+                     DELETE FROM u; \
+                   END; SELECT 1";
+        let parts = split_statements(sql, Dialect::Sqlite);
+        assert_eq!(parts.len(), 2);
+        assert!(parts[0].ends_with("END"));
+        assert_eq!(parts[1], "SELECT 1");
+    }
+
+    #[test]
+    fn split_statements_postgres_bodies_and_strings() {
+        let sql = "CREATE FUNCTION f() RETURNS int AS $body$ BEGIN RETURN 1; END; $body$ LANGUAGE plpgsql;\n\
+                   SELECT E'a\\';b', $$x;y$$, $1;\n\
+                   /* outer /* inner; */ still comment; */ SELECT 2;\n\
+                   CREATE FUNCTION g() RETURNS int LANGUAGE sql BEGIN ATOMIC SELECT 1; SELECT 2; END;";
+        let parts = split_statements(sql, Dialect::Postgres);
+        assert_eq!(parts.len(), 4);
+        assert!(parts[0].ends_with("LANGUAGE plpgsql"));
+        assert!(parts[1].starts_with("SELECT E'"));
+        assert!(parts[2].ends_with("SELECT 2"));
+        assert!(parts[3].ends_with("END"));
+    }
+
+    #[test]
+    fn split_statements_mysql_delimiter_and_escapes() {
+        let sql = "SELECT 'a\\';b' # hash; comment\n;\n\
+                   DELIMITER //\n\
+                   CREATE PROCEDURE p() BEGIN SELECT 1; SELECT 2; END//\n\
+                   DELIMITER ;\n\
+                   SELECT `x;y` FROM t;";
+        let parts = split_statements(sql, Dialect::MySql);
+        assert_eq!(parts.len(), 3);
+        assert!(parts[0].starts_with("SELECT 'a"));
+        assert_eq!(parts[1], "CREATE PROCEDURE p() BEGIN SELECT 1; SELECT 2; END");
+        assert_eq!(parts[2], "SELECT `x;y` FROM t");
+    }
+}
@@ -0,0 +1,696 @@
+use std::str::FromStr;
+
+use anyhow::{bail, Result};
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use sqlx::types::BigDecimal;
+
+use super::builder::{
+    AggregateFunc, BulkInsertSpec, DeleteSpec, FilterCond, FilterNode, InsertSpec, SelectSpec, Sort, Spec, UpdateSpec,
+};
+use super::schema::{ColumnDef, DatabaseSchema, TableDef};
+
+/// A problem with one field of a spec. `field` is a path into the spec as
+/// sent, e.g. `filters[0].any[1].value` or `columns[2]`.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct SpecError {
+    pub field: String,
+    pub message: String,
+}
+
+/// A spec that can be checked against the schema. Checking also converts
+/// its values to the column types with [`coerce`], as `changeset::plan`
+/// does, so the spec is built from what was checked.
+pub trait Check {
+    /// Check against the schema, coercing values in place; empty when valid.
+    fn check(&mut self, db: &DatabaseSchema) -> Vec<SpecError>;
+}
+
+impl Check for Spec {
+    fn check(&mut self, db: &DatabaseSchema) -> Vec<SpecError> {
+        match self {
+            Spec::Select(s) => check_select(db, s),
+            Spec::Insert(s) => check_insert(db, s),
+            Spec::BulkInsert(s) => check_bulk_insert(db, s),
+            Spec::Update(s) => check_update(db, s),
+            Spec::Delete(s) => check_delete(db, s),
+        }
+    }
+}
+
+impl Check for SelectSpec {
+    fn check(&mut self, db: &DatabaseSchema) -> Vec<SpecError> {
+        check_select(db, self)
+    }
+}
+
+impl Check for InsertSpec {
+    fn check(&mut self, db: &DatabaseSchema) -> Vec<SpecError> {
+        check_insert(db, self)
+    }
+}
+
+impl Check for BulkInsertSpec {
+    fn check(&mut self, db: &DatabaseSchema) -> Vec<SpecError> {
+        check_bulk_insert(db, self)
+    }
+}
+
+impl Check for UpdateSpec {
+    fn check(&mut self, db: &DatabaseSchema) -> Vec<SpecError> {
+        check_update(db, self)
+    }
+}
+
+impl Check for DeleteSpec {
+    fn check(&mut self, db: &DatabaseSchema) -> Vec<SpecError> {
+        check_delete(db, self)
+    }
+}
+
+/// Fail with every error listed when there are any.
+pub fn ensure(errors: Vec<SpecError>) -> Result<()> {
+    if errors.is_empty() {
+        return Ok(());
+    }
+    let list: Vec<String> = errors.iter().map(|e| format!("{}: {}", e.field, e.message)).collect();
+    bail!("invalid spec: {}", list.join("; "))
+}
+
+/// Find a table by name, within `schema` when one is given.
+pub fn find_table<'a>(tables: &'a [TableDef], schema: Option<&str>, name: &str) -> Option<&'a TableDef> {
+    let schema = schema.filter(|s| !s.is_empty());
+    tables.iter().find(|t| t.name == name && schema.is_none_or(|s| s == t.schema))
+}
+
+/// Look up a column of `table` by name.
+pub fn column<'a>(table: &'a TableDef, name: &str) -> Result<&'a ColumnDef, String> {
+    table
+        .columns
+        .iter()
+        .find(|c| c.name == name)
+        .ok_or_else(|| format!("column {} not found in {}", name, table.name))
+}
+
+fn err(field: impl Into<String>, message: impl Into<String>) -> SpecError {
+    SpecError { field: field.into(), message: message.into() }
+}
+
+fn table_of<'a>(db: &'a DatabaseSchema, schema: &Option<String>, table: &str) -> Result<&'a TableDef, SpecError> {
+    find_table(&db.tables, schema.as_deref(), table).ok_or_else(|| match schema {
+        Some(s) => err("table", format!("table {}.{} not found", s, table)),
+        None => err("table", format!("table {} not found", table)),
+    })
+}
+
+pub fn check_select(db: &DatabaseSchema, spec: &mut SelectSpec) -> Vec<SpecError> {
+    let base = match table_of(db, &spec.schema, &spec.table) {
+        Ok(t) => t,
+        Err(e) => return vec![e],
+    };
+    let mut errors = Vec::new();
+    // conditions are coerced in place, so they are taken out while the
+    // column lookups borrow the rest of the spec
+    let mut filters = std::mem::take(&mut spec.filters);
+    let mut having = std::mem::take(&mut spec.having);
+
+    // tables in scope by alias
+    let mut scope: Vec<(&str, &TableDef)> = vec![(&spec.table, base)];
+    for (i, j) in spec.joins.iter().enumerate() {
+        let fk = &j.foreign_key;
+        let from = j.from.as_deref().unwrap_or(&spec.table);
+        if fk.columns.is_empty() || fk.columns.len() != fk.ref_columns.len() {
+            errors.push(err(
+                format!("joins[{}].foreign_key", i),
+                "columns and ref_columns must pair up",
+            ));
+        }
+        match scope.iter().find(|(a, _)| *a == from) {
+            Some((_, t)) => {
+                for (k, c) in fk.columns.iter().enumerate() {
+                    if let Err(m) = column(t, c) {
+                        errors.push(err(format!("joins[{}].foreign_key.columns[{}]", i, k), m));
+                    }
+                }
+                // joins follow introspected foreign keys, not any column pair
+                let declared = t.foreign_keys.iter().any(|d| {
+                    d.columns == fk.columns
+                        && d.ref_columns == fk.ref_columns
+                        && d.to_table == fk.to_table
+                        && (fk.ref_schema.is_none() || d.ref_schema == fk.ref_schema)
+                });
+                if !declared {
+                    errors.push(err(
+                        format!("joins[{}].foreign_key", i),
+                        format!(
+                            "{} has no foreign key ({}) referencing {} ({})",
+                            t.name,
+                            fk.columns.join(", "),
+                            fk.to_table,
+                            fk.ref_columns.join(", ")
+                        ),
+                    ));
+                }
+            }
+            None => errors.push(err(format!("joins[{}].from", i), format!("unknown table alias {}", from))),
+        }
+        match find_table(&db.tables, fk.ref_schema.as_deref(), &fk.to_table) {
+            Some(t) => {
+                for (k, c) in fk.ref_columns.iter().enumerate() {
+                    if let Err(m) = column(t, c) {
+                        errors.push(err(format!("joins[{}].foreign_key.ref_columns[{}]", i, k), m));
+                    }
+                }
+                scope.push((j.alias(), t));
+            }
+            None => errors.push(err(
+                format!("joins[{}].foreign_key.to_table", i),
+                format!("table {} not found", fk.to_table),
+            )),
+        }
+    }
+
+    let lookup = |name: &str| -> Result<ColumnDef, String> {
+        let (alias, col) = spec.resolve(name);
+        match scope.iter().find(|(a, _)| *a == alias) {
+            Some((_, t)) => column(t, col).cloned(),
+            None => Err(format!("table {} not found", alias)),
+        }
+    };
+
+    for (i, c) in spec.columns.iter().enumerate() {
+        if let Err(m) = lookup(c) {
+            errors.push(err(format!("columns[{}]", i), m));
+        }
+    }
+    for (i, g) in spec.group_by.iter().enumerate() {
+        if let Err(m) = lookup(g) {
+            errors.push(err(format!("group_by[{}]", i), m));
+        }
+    }
+    for (i, a) in spec.aggregates.iter().enumerate() {
+        let field = format!("aggregates[{}].column", i);
+        match (&a.column, a.func) {
+            (None, AggregateFunc::Count) => {}
+            (None, _) => errors.push(err(field, "a column is required")),
+            (Some(c), func) => match lookup(c) {
+                Ok(col) => {
+                    let numeric = matches!(family(&col.data_type), Family::Integer | Family::Number | Family::Other);
+                    if matches!(func, AggregateFunc::Sum | AggregateFunc::Avg) && !numeric {
+                        errors.push(err(field, format!("{} is {}, not a number", c, col.data_type)));
+                    }
+                }
+                Err(m) => errors.push(err(field, m)),
+            },
+        }
+    }
+
+    for (i, f) in filters.iter_mut().enumerate() {
+        check_node(f, format!("filters[{}]", i), &lookup, &mut errors);
+    }
+    // in HAVING a condition may name an aggregate by its alias
+    let having_lookup = |name: &str| -> Result<ColumnDef, String> {
+        let Some(a) = spec.aggregates.iter().find(|a| a.alias() == name) else {
+            return lookup(name);
+        };
+        let data_type = match a.func {
+            AggregateFunc::Count | AggregateFunc::CountDistinct => "bigint".to_string(),
+            AggregateFunc::Sum | AggregateFunc::Avg => "numeric".to_string(),
+            AggregateFunc::Min | AggregateFunc::Max => match &a.column {
+                Some(c) => lookup(c)?.data_type,
+                None => "numeric".to_string(),
+            },
+        };
+        Ok(ColumnDef {
+            name: name.to_string(),
+            data_type,
+            nullable: true,
+            default: None,
+            is_pk: false,
+            length: None,
+            precision: None,
+            scale: None,
+        })
+    };
+    for (i, f) in having.iter_mut().enumerate() {
+        check_node(f, format!("having[{}]", i), &having_lookup, &mut errors);
+    }
+
+    for (i, k) in spec.keyset.iter().flat_map(|k| k.unique_key.iter()).enumerate() {
+        if let Err(m) = lookup(k) {
+            errors.push(err(format!("keyset.unique_key[{}]", i), m));
+        }
+    }
+
+    match &spec.sort {
+        Some(Sort::Single(c, _)) => {
+            if let Err(m) = lookup(c) {
+                errors.push(err("sort", m));
+            }
+        }
+        Some(Sort::Keys(keys)) => {
+            for (i, k) in keys.iter().enumerate() {
+                if let Err(m) = lookup(&k.column) {
+                    errors.push(err(format!("sort[{}].column", i), m));
+                }
+            }
+        }
+        None => {}
+    }
+    spec.filters = filters;
+    spec.having = having;
+    errors
+}
+
+pub fn check_insert(db: &DatabaseSchema, spec: &mut InsertSpec) -> Vec<SpecError> {
+    let table = match table_of(db, &spec.schema, &spec.table) {
+        Ok(t) => t,
+        Err(e) => return vec![e],
+    };
+    let mut errors = check_values(table, &mut spec.values);
+    for c in &table.columns {
+        if !c.nullable && c.default.is_none() && !c.is_pk && !spec.values.iter().any(|(n, _)| n == &c.name) {
+            errors.push(err("values", format!("column {} is NOT NULL and has no default", c.name)));
+        }
+    }
+    errors
+}
+
+pub fn check_bulk_insert(db: &DatabaseSchema, spec: &mut BulkInsertSpec) -> Vec<SpecError> {
+    let table = match table_of(db, &spec.schema, &spec.table) {
+        Ok(t) => t,
+        Err(e) => return vec![e],
+    };
+    let mut errors = Vec::new();
+    let mut cols = Vec::new();
+    for (i, name) in spec.columns.iter().enumerate() {
+        match column(table, name) {
+            Ok(c) => cols.push(Some(c)),
+            Err(m) => {
+                errors.push(err(format!("columns[{}]", i), m));
+                cols.push(None);
+            }
+        }
+    }
+    for c in &table.columns {
+        if !c.nullable && c.default.is_none() && !c.is_pk && !spec.columns.contains(&c.name) {
+            errors.push(err("columns", format!("column {} is NOT NULL and has no default", c.name)));
+        }
+    }
+
+    if spec.rows.is_empty() {
+        errors.push(err("rows", "no rows to insert"));
+    }
+    for (r, row) in spec.rows.iter_mut().enumerate() {
+        if row.len() != spec.columns.len() {
+            errors.push(err(format!("rows[{}]", r), format!("{} values for {} columns", row.len(), spec.columns.len())));
+            continue;
+        }
+        for (i, (v, c)) in row.iter_mut().zip(&cols).enumerate() {
+            match c.map(|c| coerce(v, c)) {
+                Some(Ok(coerced)) => *v = coerced,
+                Some(Err(m)) => errors.push(err(format!("rows[{}][{}]", r, i), m)),
+                None => {}
+            }
+        }
+    }
+
+    if let Some(u) = &spec.upsert {
+        if u.conflict.is_empty() {
+            errors.push(err("upsert.conflict", "name the key columns a conflict is detected on"));
+        }
+        for (i, name) in u.conflict.iter().enumerate() {
+            if let Err(m) = column(table, name) {
+                errors.push(err(format!("upsert.conflict[{}]", i), m));
+            }
+        }
+        // updated columns take the value of the row being inserted
+        for (i, name) in u.update.iter().enumerate() {
+            if !spec.columns.contains(name) {
+                errors.push(err(format!("upsert.update[{}]", i), format!("column {} is not inserted", name)));
+            }
+        }
+    }
+    errors
+}
+
+pub fn check_update(db: &DatabaseSchema, spec: &mut UpdateSpec) -> Vec<SpecError> {
+    let table = match table_of(db, &spec.schema, &spec.table) {
+        Ok(t) => t,
+        Err(e) => return vec![e],
+    };
+    let mut errors = check_values(table, &mut spec.values);
+    check_filters(table, &mut spec.filters, &mut errors);
+    errors
+}
+
+pub fn check_delete(db: &DatabaseSchema, spec: &mut DeleteSpec) -> Vec<SpecError> {
+    let table = match table_of(db, &spec.schema, &spec.table) {
+        Ok(t) => t,
+        Err(e) => return vec![e],
+    };
+    let mut errors = Vec::new();
+    check_filters(table, &mut spec.filters, &mut errors);
+    errors
+}
+
+fn check_values(table: &TableDef, values: &mut [(String, JsonValue)]) -> Vec<SpecError> {
+    let mut errors = Vec::new();
+    for (i, (name, v)) in values.iter_mut().enumerate() {
+        match column(table, name).and_then(|c| coerce(v, c)) {
+            Ok(coerced) => *v = coerced,
+            Err(m) => errors.push(err(format!("values[{}]", i), m)),
+        }
+    }
+    errors
+}
+
+fn check_filters(table: &TableDef, filters: &mut [FilterCond], errors: &mut Vec<SpecError>) {
+    for (i, f) in filters.iter_mut().enumerate() {
+        let field = format!("filters[{}]", i);
+        match column(table, &f.column) {
+            Ok(col) => check_cond(f, &field, col, errors),
+            Err(m) => errors.push(err(field + ".column", m)),
+        }
+    }
+}
+
+fn check_node(
+    node: &mut FilterNode,
+    field: String,
+    lookup: &dyn Fn(&str) -> Result<ColumnDef, String>,
+    errors: &mut Vec<SpecError>,
+) {
+    match node {
+        FilterNode::All { all } => {
+            for (i, n) in all.iter_mut().enumerate() {
+                check_node(n, format!("{}.all[{}]", field, i), lookup, errors);
+            }
+        }
+        FilterNode::Any { any } => {
+            for (i, n) in any.iter_mut().enumerate() {
+                check_node(n, format!("{}.any[{}]", field, i), lookup, errors);
+            }
+        }
+        FilterNode::Not { not } => check_node(not, format!("{}.not", field), lookup, errors),
+        FilterNode::Cond(cond) => match lookup(&cond.column) {
+            Ok(col) => check_cond(cond, &field, &col, errors),
+            Err(m) => errors.push(err(field + ".column", m)),
+        },
+    }
+}
+
+// Check the operator against the column's type and the value's shape, and
+// coerce the compared values.
+fn check_cond(cond: &mut FilterCond, field: &str, col: &ColumnDef, errors: &mut Vec<SpecError>) {
+    let value_field = format!("{}.value", field);
+    let op_field = format!("{}.op", field);
+    let fam = family(&col.data_type);
+    let mut scalar = |v: &mut JsonValue, field: String| match coerce(v, col) {
+        Ok(coerced) => *v = coerced,
+        Err(m) => errors.push(err(field, m)),
+    };
+    match cond.op.as_str() {
+        "=" | "!=" | ">" | ">=" | "<" | "<=" => {
+            if cond.value.is_null() {
+                errors.push(err(value_field, "NULL never compares equal; use is_null or is_not_null"));
+            } else {
+                scalar(&mut cond.value, value_field);
+            }
+        }
+        "like" | "not_like" | "ilike" | "not_ilike" | "contains" | "starts_with" | "ends_with" | "regex" => {
+            if matches!(fam, Family::Integer | Family::Number | Family::Bool | Family::Temporal) {
+                errors.push(err(op_field, format!("{} needs a text column; {} is {}", cond.op, col.name, col.data_type)));
+            }
+            if !cond.value.is_string() {
+                errors.push(err(value_field, "expected a string"));
+            }
+        }
+        "is_null" | "is_not_null" => {}
+        "in" | "not_in" => match &mut cond.value {
+            JsonValue::Array(vs) => {
+                for (i, v) in vs.iter_mut().enumerate() {
+                    scalar(v, format!("{}[{}]", value_field, i));
+                }
+            }
+            _ => errors.push(err(value_field, "expected a list of values")),
+        },
+        "between" | "not_between" => match cond.value.as_array_mut().map(Vec::as_mut_slice) {
+            Some([low, high]) => {
+                scalar(low, format!("{}[0]", value_field));
+                scalar(high, format!("{}[1]", value_field));
+            }
+            _ => errors.push(err(value_field, "expected [low, high]")),
+        },
+        "in_last_days" | "older_than_days" => {
+            if matches!(fam, Family::Integer | Family::Number | Family::Bool) {
+                errors.push(err(op_field, format!("{} needs a date/time column; {} is {}", cond.op, col.name, col.data_type)));
+            }
+            if cond.value.as_u64().is_none_or(|n| n > i32::MAX as u64) {
+                errors.push(err(value_field, "expected a whole number of days"));
+            }
+        }
+        op => errors.push(err(op_field, format!("unknown operator {}", op))),
+    }
+}
+
+// Broad type families, judged from the declared type name of each dialect.
+#[derive(Debug, PartialEq)]
+enum Family {
+    Integer,
+    Number,
+    Bool,
+    Text,
+    Temporal,
+    Other,
+}
+
+fn family(data_type: &str) -> Family {
+    let t = data_type.to_ascii_lowercase();
+    // MySQL's BOOLEAN is TINYINT(1)
+    if t.starts_with("bool") || t.starts_with("tinyint(1)") {
+        Family::Bool
+    } else if t.contains("int") && !t.contains("interval") && !t.contains("point") {
+        Family::Integer
+    } else if ["numeric", "decimal", "real", "double", "float", "money"].iter().any(|n| t.contains(n)) {
+        Family::Number
+    } else if t.contains("date") || t.contains("time") {
+        Family::Temporal
+    } else if ["char", "text", "clob", "string"].iter().any(|n| t.contains(n)) {
+        Family::Text
+    } else {
+        Family::Other
+    }
+}
+
+fn exact_decimal(data_type: &str) -> bool {
+    let t = data_type.to_ascii_lowercase();
+    ["numeric", "decimal", "money"].iter().any(|n| t.contains(n))
+}
+
+/// Coerce a cell value to the column's type family, e.g. `"42"` for an
+/// integer column becomes `42`. NULL is rejected for NOT NULL columns.
+/// NUMERIC/DECIMAL text stays text when a double would round it.
+pub fn coerce(v: &JsonValue, col: &ColumnDef) -> Result<JsonValue, String> {
+    if v.is_null() {
+        return if col.nullable { Ok(JsonValue::Null) } else { Err("column is NOT NULL".into()) };
+    }
+    let bad = || format!("cannot convert {} to {}", v, col.data_type);
+    match family(&col.data_type) {
+        Family::Integer => match v {
+            JsonValue::Number(n) if n.is_i64() || n.is_u64() => Ok(v.clone()),
+            JsonValue::Number(n) => match n.as_f64() {
+                Some(f) if f.fract() == 0.0 && f.abs() < i64::MAX as f64 => Ok((f as i64).into()),
+                _ => Err(bad()),
+            },
+            JsonValue::String(s) => s.trim().parse::<i64>().map(Into::into).map_err(|_| bad()),
+            JsonValue::Bool(b) => Ok((*b as i64).into()),
+            _ => Err(bad()),
+        },
+        Family::Number => match v {
+            JsonValue::Number(_) => Ok(v.clone()),
+            JsonValue::String(s) if exact_decimal(&col.data_type) => {
+                let s = s.trim();
+                let d = BigDecimal::from_str(s).map_err(|_| bad())?;
+                // keep the text unless a double holds the value exactly
+                let f = s.parse::<f64>().ok().filter(|f| BigDecimal::from_str(&f.to_string()).is_ok_and(|x| x == d));
+                Ok(f.and_then(serde_json::Number::from_f64).map_or_else(|| s.into(), JsonValue::Number))
+            }
+            JsonValue::String(s) => s
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .and_then(serde_json::Number::from_f64)
+                .map(JsonValue::Number)
+                .ok_or_else(bad),
+            _ => Err(bad()),
+        },
+        Family::Bool => match v {
+            JsonValue::Bool(_) => Ok(v.clone()),
+            JsonValue::Number(n) if n.as_i64() == Some(0) || n.as_i64() == Some(1) => {
+                Ok(JsonValue::Bool(n.as_i64() == Some(1)))
+            }
+            JsonValue::String(s) => match s.trim().to_ascii_lowercase().as_str() {
+                "true" | "t" | "1" | "yes" => Ok(JsonValue::Bool(true)),
+                "false" | "f" | "0" | "no" => Ok(JsonValue::Bool(false)),
+                _ => Err(bad()),
+            },
+            _ => Err(bad()),
+        },
+        Family::Text => match v {
+            JsonValue::String(_) => Ok(v.clone()),
+            JsonValue::Number(_) | JsonValue::Bool(_) => Ok(JsonValue::String(v.to_string())),
+            _ => Err(bad()),
+        },
+        Family::Temporal | Family::Other => Ok(v.clone()),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::db::schema::ForeignKeyDef;
+    use crate::db::builder::InsertSpec;
+    use crate::db::{check_spec, edit, execute_sql, TempDb};
+
+    /// Column fixture shared by the spec tests.
+    pub(crate) fn col(name: &str, data_type: &str, nullable: bool, is_pk: bool) -> ColumnDef {
+        ColumnDef {
+            name: name.into(),
+            data_type: data_type.into(),
+            nullable,
+            default: None,
+            is_pk,
+            length: None,
+            precision: None,
+            scale: None,
+        }
+    }
+
+    #[test]
+    fn coerce_follows_column_type_family() {
+        let int = col("n", "int4", true, false);
+        assert_eq!(coerce(&"42".into(), &int), Ok(42.into()));
+        assert!(coerce(&"4.2".into(), &int).is_err());
+        assert_eq!(coerce(&"yes".into(), &col("b", "BOOLEAN", true, false)), Ok(true.into()));
+        assert_eq!(coerce(&1.into(), &col("b", "tinyint(1)", true, false)), Ok(true.into()));
+        assert_eq!(coerce(&7.into(), &col("s", "VARCHAR(10)", true, false)), Ok("7".into()));
+        assert_eq!(coerce(&JsonValue::Null, &col("s", "text", false, false)), Err("column is NOT NULL".into()));
+        assert_eq!(coerce(&"2024-01-01".into(), &col("d", "date", true, false)), Ok("2024-01-01".into()));
+        let dec = col("d", "NUMERIC(20,2)", true, false);
+        assert_eq!(coerce(&"12.5".into(), &dec), Ok(12.5.into()));
+        assert_eq!(coerce(&"12345678901234567.01".into(), &dec), Ok("12345678901234567.01".into()));
+        assert!(coerce(&"12,5".into(), &dec).is_err());
+        assert_eq!(coerce(&"0.1".into(), &col("f", "REAL", true, false)), Ok(0.1.into()));
+    }
+
+    #[test]
+    fn select_errors_point_at_fields() {
+        let table = |name: &str, columns, foreign_keys| TableDef {
+            schema: "public".into(),
+            name: name.into(),
+            type_: "table".into(),
+            columns,
+            foreign_keys,
+            indexes: vec![],
+            unique_constraints: vec![],
+            checks: vec![],
+        };
+        let fk = ForeignKeyDef {
+            name: None,
+            columns: vec!["customer_id".into()],
+            ref_schema: None,
+            to_table: "customers".into(),
+            ref_columns: vec!["id".into()],
+            on_update: None,
+            on_delete: None,
+        };
+        let db = DatabaseSchema {
+            dialect: "postgres".into(),
+            schemas: vec!["public".into()],
+            tables: vec![
+                table(
+                    "orders",
+                    vec![col("id", "int4", false, true), col("customer_id", "int4", true, false), col("placed", "timestamp", true, false)],
+                    vec![fk.clone()],
+                ),
+                table("customers", vec![col("id", "int4", false, true), col("name", "text", true, false)], vec![]),
+            ],
+        };
+        let spec = |v: serde_json::Value| -> SelectSpec { serde_json::from_value(v).unwrap() };
+
+        let mut ok = spec(serde_json::json!({
+            "table": "orders", "columns": ["id", "c.name"], "sort": [{ "column": "placed" }], "limit": null, "offset": null,
+            "joins": [{ "foreign_key": fk, "from": null, "alias": "c" }],
+            "filters": [{ "any": [
+                { "column": "c.name", "op": "contains", "value": "ann" },
+                { "column": "placed", "op": "in_last_days", "value": 7 }
+            ] }, { "column": "id", "op": "in", "value": ["1", 2] }]
+        }));
+        assert_eq!(check_select(&db, &mut ok), vec![]);
+        // values are built as checked, so a Postgres int4 is not bound as text
+        let FilterNode::Cond(id) = &ok.filters[1] else { panic!("expected a condition") };
+        assert_eq!(id.value, serde_json::json!([1, 2]));
+
+        let mut bad = spec(serde_json::json!({
+            "table": "orders", "columns": ["id", "total"], "sort": ["nope", true], "limit": null, "offset": null,
+            "filters": [
+                { "column": "id", "op": "=", "value": "abc" },
+                { "not": { "column": "id", "op": "starts_with", "value": "1" } },
+                { "column": "placed", "op": "near", "value": 1 }
+            ]
+        }));
+        let fields: Vec<String> = check_select(&db, &mut bad).into_iter().map(|e| e.field).collect();
+        assert_eq!(fields, ["columns[1]", "filters[0].value", "filters[1].not.op", "filters[2].op", "sort"]);
+
+        // a column pair that no introspected foreign key declares is refused
+        let undeclared = ForeignKeyDef { columns: vec!["id".into()], ..fk.clone() };
+        let mut stray = spec(serde_json::json!({
+            "table": "orders", "columns": ["id"], "filters": [], "sort": null, "limit": null, "offset": null,
+            "joins": [{ "foreign_key": undeclared, "from": null, "alias": "c" }]
+        }));
+        let fields: Vec<String> = check_select(&db, &mut stray).into_iter().map(|e| e.field).collect();
+        assert_eq!(fields, ["joins[0].foreign_key"]);
+
+        let mut missing = spec(serde_json::json!({
+            "schema": "sales", "table": "orders", "columns": [], "filters": [], "sort": null, "limit": null, "offset": null
+        }));
+        assert_eq!(check_select(&db, &mut missing), vec![err("table", "table sales.orders not found")]);
+    }
+
+    /// Specs are checked against the cached schema, which DDL run through the
+    /// app invalidates.
+    #[tokio::test]
+    async fn specs_validate_against_cached_schema_sqlite() -> anyhow::Result<()> {
+        let db = TempDb::open("validate").await?;
+        execute_sql(&db.reg, &db.conn_id, "CREATE TABLE t (id INTEGER PRIMARY KEY, n INTEGER NOT NULL)", 10, None, None).await?;
+
+        let select = |table: &str, column: &str| -> Spec {
+            serde_json::from_value(serde_json::json!({
+                "kind": "select", "table": table, "columns": [column], "filters": [], "sort": null, "limit": null, "offset": null
+            }))
+            .unwrap()
+        };
+        assert!(check_spec(&db.reg, &db.conn_id, &mut select("t", "n")).await?.is_empty());
+        assert!(db.reg.schemas.lock().unwrap().contains_key(&db.conn_id));
+
+        execute_sql(&db.reg, &db.conn_id, "ALTER TABLE t ADD COLUMN label TEXT", 10, None, None).await?;
+        assert!(!db.reg.schemas.lock().unwrap().contains_key(&db.conn_id));
+        assert!(check_spec(&db.reg, &db.conn_id, &mut select("t", "label")).await?.is_empty());
+        let errors = check_spec(&db.reg, &db.conn_id, &mut select("t", "missing")).await?;
+        assert_eq!(errors[0].field, "columns[0]");
+
+        let bad = InsertSpec { schema: None, table: "t".into(), values: vec![("n".into(), serde_json::json!("many"))] };
+        let err = edit::insert(&db.reg, &db.conn_id, None, &bad).await.err().unwrap();
+        assert!(err.to_string().starts_with("invalid spec: values[0]: cannot convert"), "{err}");
+        assert_eq!(execute_sql(&db.reg, &db.conn_id, "SELECT count(*) FROM t", 10, None, None).await?.rows[0][0], serde_json::json!(0));
+
+        // the insert binds the coerced values, not the JSON as sent
+        execute_sql(&db.reg, &db.conn_id, "ALTER TABLE t ADD COLUMN done BOOLEAN", 10, None, None).await?;
+        let values = vec![("n".into(), serde_json::json!("42")), ("done".into(), serde_json::json!("yes"))];
+        edit::insert(&db.reg, &db.conn_id, None, &InsertSpec { schema: None, table: "t".into(), values }).await?;
+        let stored = execute_sql(&db.reg, &db.conn_id, "SELECT typeof(n), done FROM t", 10, None, None).await?;
+        assert_eq!(stored.rows[0], [serde_json::json!("integer"), serde_json::json!(true)]);
+        Ok(())
+    }
+}
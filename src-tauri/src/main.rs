@@ -16,8 +16,28 @@ fn main() {
             api::open_connection,
             api::close_connection,
             api::execute_sql,
+            api::execute_sql_stream,
+            api::execute_script,
             api::get_schema,
+            api::schema_changed,
+            api::list_objects,
+            api::describe_table,
+            api::describe_tables,
             api::execute_select_spec,
+            api::execute_select_spec_stream,
+            api::validate_spec,
+            api::preview_spec_sql,
+            api::execute_insert_spec,
+            api::execute_bulk_insert,
+            api::execute_update_spec,
+            api::execute_delete_spec,
+            api::preview_change_set,
+            api::apply_change_set,
+            api::cancel_query,
+            api::begin_transaction,
+            api::commit,
+            api::rollback,
+            api::transaction_state,
             // Utils
             api::open_sqlite_dialog,
             api::begin_security_scoped_access,
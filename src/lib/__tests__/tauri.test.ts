@@ -11,10 +11,22 @@ import {
   closeConnection,
   executeSql,
   executeSelectSpec,
+  executeScript,
+  executeUpdateSpec,
+  executeBulkInsert,
+  validateSpec,
+  previewSpecSql,
+  applyChangeSet,
+  beginTransaction,
+  commit,
   getSchema,
+  schemaChanged,
+  listObjects,
+  describeTables,
   openSqliteDialog,
   fileSize,
   type QueryResult,
+  type ScriptResult,
   type SelectSpec,
 } from '@/lib/tauri';
 
@@ -46,8 +58,16 @@ describe('tauri thin client wrappers', () => {
   it('executeSql returns QueryResult', async () => {
     const qr: QueryResult = {
       columns: ['id', 'name'],
+      column_meta: [
+        { name: 'id', type: 'INTEGER', nullable: false, table: null, column: null },
+        { name: 'name', type: 'TEXT', nullable: true, table: null, column: null },
+      ],
       rows: [[1, 'alice']],
       truncated: false,
+      kind: 'query',
+      rows_affected: null,
+      last_insert_id: null,
+      elapsed_ms: 0.4,
     };
     resolved(qr);
     const res = await executeSql('conn-1', 'select 1', 100);
@@ -58,7 +78,18 @@ describe('tauri thin client wrappers', () => {
   });
 
   it('executeSelectSpec passes spec and returns QueryResult', async () => {
-    const qr: QueryResult = { columns: ['id'], rows: [[1]], truncated: false };
+    const qr: QueryResult = {
+      columns: ['id'],
+      column_meta: [
+        { name: 'id', type: 'INTEGER', nullable: null, table: 'users', column: 'id' },
+      ],
+      rows: [[1]],
+      truncated: false,
+      kind: 'query',
+      rows_affected: null,
+      last_insert_id: null,
+      elapsed_ms: 0.2,
+    };
     resolved(qr);
     const spec: SelectSpec = {
       table: 'users',
@@ -75,6 +106,159 @@ describe('tauri thin client wrappers', () => {
     });
   });
 
+  it('executeSelectSpec asks for a row count', async () => {
+    resolved({ rows: [], total: { rows: 42, exact: true } });
+    const spec: SelectSpec = { table: 'users', columns: [], filters: [], limit: 10 };
+    const res = await executeSelectSpec('conn-1', spec, undefined, undefined, 'exact');
+    expect(res.total).toEqual({ rows: 42, exact: true });
+    expect(invoke).toHaveBeenCalledWith('execute_select_spec', {
+      args: { conn_id: 'conn-1', spec, count: 'exact' },
+    });
+  });
+
+  it('executeScript flattens options into args', async () => {
+    const sr: ScriptResult = {
+      statements: [
+        { index: 0, sql: 'select 1', result: null, error: 'boom' },
+      ],
+      total: 2,
+      committed: false,
+      elapsed_ms: 1.5,
+    };
+    resolved(sr);
+    const res = await executeScript('conn-1', 'select 1; select 2', {
+      transaction: true,
+    });
+    expect(res).toEqual(sr);
+    expect(invoke).toHaveBeenCalledWith('execute_script', {
+      args: {
+        conn_id: 'conn-1',
+        sql: 'select 1; select 2',
+        limit: 1000,
+        transaction: true,
+        query_id: undefined,
+      },
+    });
+  });
+
+  it('executeUpdateSpec defaults to the primary key guard', async () => {
+    const qr: QueryResult = {
+      columns: [],
+      column_meta: [],
+      rows: [],
+      truncated: false,
+      kind: 'update',
+      rows_affected: 1,
+      last_insert_id: null,
+      elapsed_ms: 0.3,
+    };
+    resolved(qr);
+    const spec = {
+      table: 'users',
+      values: [['name', 'bob']] as Array<[string, unknown]>,
+      filters: [{ column: 'id', op: '=' as const, value: 1 }],
+    };
+    const res = await executeUpdateSpec('conn-1', spec);
+    expect(res).toEqual(qr);
+    expect(invoke).toHaveBeenCalledWith('execute_update_spec', {
+      args: { conn_id: 'conn-1', spec, allow_unpinned: false },
+    });
+  });
+
+  it('executeBulkInsert passes rows and upsert keys', async () => {
+    resolved({ rows: [], rows_affected: 2 });
+    const spec = {
+      table: 'users',
+      columns: ['id', 'name'],
+      rows: [
+        [1, 'ann'],
+        [2, 'bob'],
+      ],
+      upsert: { conflict: ['id'] },
+    };
+    const res = await executeBulkInsert('conn-1', spec, 'sess-1');
+    expect(res.rows_affected).toBe(2);
+    expect(invoke).toHaveBeenCalledWith('execute_bulk_insert', {
+      args: { conn_id: 'conn-1', spec, session_id: 'sess-1' },
+    });
+  });
+
+  it('validateSpec returns field-level errors', async () => {
+    const errors = [{ field: 'columns[0]', message: 'column nope not found in users' }];
+    resolved(errors);
+    const spec = {
+      kind: 'select' as const,
+      table: 'users',
+      columns: ['nope'],
+      filters: [],
+    };
+    const res = await validateSpec('conn-1', spec);
+    expect(res).toEqual(errors);
+    expect(invoke).toHaveBeenCalledWith('validate_spec', {
+      args: { conn_id: 'conn-1', spec },
+    });
+  });
+
+  it('previewSpecSql returns inlined and parameterized SQL', async () => {
+    const out = {
+      sql: `DELETE FROM "t" WHERE "t"."id" = 1`,
+      parameterized: `DELETE FROM "t" WHERE "t"."id" = ?`,
+      binds: [1],
+    };
+    resolved(out);
+    const spec = {
+      kind: 'delete' as const,
+      table: 't',
+      filters: [{ column: 'id', op: '=' as const, value: 1 }],
+    };
+    const res = await previewSpecSql('conn-1', spec);
+    expect(res).toEqual(out);
+    expect(invoke).toHaveBeenCalledWith('preview_spec_sql', {
+      args: { conn_id: 'conn-1', spec },
+    });
+  });
+
+  it('applyChangeSet returns the failing change', async () => {
+    const out = {
+      sql: ['UPDATE "t" SET "n" = 1 WHERE "t"."id" = 1'],
+      applied: false,
+      rows_affected: 0,
+      error: { index: 0, column: 'n', message: 'column is NOT NULL' },
+    };
+    resolved(out);
+    const changeSet = {
+      table: 't',
+      changes: [
+        {
+          kind: 'update' as const,
+          key: [['id', 1]] as Array<[string, unknown]>,
+          values: [['n', null]] as Array<[string, unknown]>,
+        },
+      ],
+    };
+    const res = await applyChangeSet('conn-1', changeSet);
+    expect(res).toEqual(out);
+    expect(invoke).toHaveBeenCalledWith('apply_change_set', {
+      args: { conn_id: 'conn-1', change_set: changeSet },
+    });
+  });
+
+  it('beginTransaction returns a session and commit ends it', async () => {
+    const state = { session_id: 'tx-1', conn_id: 'conn-1', uncommitted: false };
+    resolved(state);
+    const res = await beginTransaction('conn-1');
+    expect(res).toEqual(state);
+    expect(invoke).toHaveBeenCalledWith('begin_transaction', {
+      args: { conn_id: 'conn-1' },
+    });
+
+    resolved(undefined);
+    await commit('tx-1');
+    expect(invoke).toHaveBeenCalledWith('commit', {
+      args: { session_id: 'tx-1' },
+    });
+  });
+
   it('getSchema returns a schema object', async () => {
     const schema = { dialect: 'sqlite', schemas: ['main'], tables: [] };
     resolved(schema);
@@ -85,6 +269,49 @@ describe('tauri thin client wrappers', () => {
     });
   });
 
+  it('getSchema can bypass the cache and schemaChanged polls', async () => {
+    resolved({ dialect: 'sqlite', schemas: ['main'], tables: [] });
+    await getSchema('conn-1', undefined, true);
+    expect(invoke).toHaveBeenCalledWith('get_schema', {
+      args: { conn_id: 'conn-1', refresh: true },
+    });
+
+    resolved(true);
+    expect(await schemaChanged('conn-1')).toBe(true);
+    expect(invoke).toHaveBeenCalledWith('schema_changed', {
+      args: { conn_id: 'conn-1' },
+    });
+  });
+
+  it('getSchema passes a filter and listObjects flattens it', async () => {
+    resolved({ dialect: 'postgres', schemas: ['public'], tables: [] });
+    await getSchema('conn-1', { schemas: ['public'] });
+    expect(invoke).toHaveBeenCalledWith('get_schema', {
+      args: { conn_id: 'conn-1', filter: { schemas: ['public'] } },
+    });
+
+    const list = {
+      dialect: 'postgres',
+      schemas: ['public'],
+      objects: [{ schema: 'public', name: 'orders', kind: 'table' as const }],
+    };
+    resolved(list);
+    const res = await listObjects('conn-1', { name_pattern: 'ord%' });
+    expect(res).toEqual(list);
+    expect(invoke).toHaveBeenCalledWith('list_objects', {
+      args: { conn_id: 'conn-1', name_pattern: 'ord%' },
+    });
+  });
+
+  it('describeTables asks for the named tables only', async () => {
+    resolved([]);
+    const tables = [{ schema: 'public', name: 'orders' }];
+    await describeTables('conn-1', tables);
+    expect(invoke).toHaveBeenCalledWith('describe_tables', {
+      args: { conn_id: 'conn-1', tables },
+    });
+  });
+
   it('openSqliteDialog normalises null', async () => {
     resolved(null);
     const path = await openSqliteDialog();
@@ -2,30 +2,230 @@
 // Keep this file as the single boundary where the frontend talks to Rust.
 // This improves type-safety, centralises error handling, and eases testing.
 
-import { invoke } from '@tauri-apps/api/core';
+import { Channel, invoke } from '@tauri-apps/api/core';
 
 // --- Shared types between frontend and backend ---
 export type Driver = 'sqlite' | 'postgres' | 'mysql';
 
+// Per-column metadata; `nullable` is null when the driver cannot tell, and
+// `table`/`column` are set only when the source column is known.
+export interface ColumnMeta {
+  name: string;
+  type: string;
+  nullable: boolean | null;
+  table: string | null;
+  column: string | null;
+}
+
+export type StatementKind =
+  | 'query'
+  | 'insert'
+  | 'update'
+  | 'delete'
+  | 'ddl'
+  | 'other';
+
 export interface QueryResult {
   columns: string[];
+  column_meta: ColumnMeta[];
   rows: unknown[][];
-  truncated: boolean;
+  truncated: boolean; // true if more rows exist beyond the limit
+  kind: StatementKind;
+  rows_affected: number | null; // null for queries
+  last_insert_id: number | null; // SQLite rowid / MySQL AUTO_INCREMENT
+  elapsed_ms: number;
+  page?: PageCursors | null; // set for keyset pages
+  total?: RowCount | null; // set when `executeSelectSpec` is asked to count
+}
+
+// 'exact' runs COUNT(*) with the spec's filters; 'estimate' reads the table
+// size from planner statistics and ignores filters.
+export type CountMode = 'exact' | 'estimate';
+
+export interface RowCount {
+  rows: number;
+  exact: boolean;
+}
+
+// Pass `next` as `keyset.after` (or `prev` as `keyset.before`) to fetch the
+// neighbouring page; null when there is none.
+export interface PageCursors {
+  next: string | null;
+  prev: string | null;
+}
+
+// Events pushed by the streaming commands, in order: started, columns, rows..., finished.
+export type QueryEvent =
+  | { kind: 'started'; query_id: string }
+  | { kind: 'columns'; columns: string[]; column_meta: ColumnMeta[] }
+  | { kind: 'rows'; rows: unknown[][] }
+  | { kind: 'finished'; row_count: number; truncated: boolean };
+
+export interface ScriptOptions {
+  transaction?: boolean; // commit only if every statement succeeds
+  stop_on_error?: boolean; // default true; always on inside a transaction
+}
+
+// Exactly one of `result` / `error` is set.
+export interface StatementOutcome {
+  index: number;
+  sql: string;
+  result: QueryResult | null;
+  error: string | null;
+}
+
+export interface ScriptResult {
+  statements: StatementOutcome[]; // only the statements that ran
+  total: number;
+  committed: boolean | null; // null outside transaction mode
+  elapsed_ms: number;
+}
+
+// An open transaction pinned to one connection; `uncommitted` turns true once
+// a write succeeds inside it.
+export interface TransactionState {
+  session_id: string;
+  conn_id: string;
+  uncommitted: boolean;
 }
 
 export interface FilterCond {
   column: string;
-  op: string; // '=', '!=', '>', '>=', '<', '<=', 'like', 'not_like', 'is_null', 'is_not_null', 'in', 'not_in'
+  op: FilterOp; // unknown operators are rejected by the backend
   value: unknown; // number | string | boolean | null | unknown[]
 }
 
+export type FilterOp =
+  | '=' | '!=' | '>' | '>=' | '<' | '<='
+  | 'like' | 'not_like' | 'ilike' | 'not_ilike'
+  | 'contains' | 'starts_with' | 'ends_with' | 'regex' // contains is case-insensitive
+  | 'is_null' | 'is_not_null' | 'in' | 'not_in'
+  | 'between' | 'not_between' // value: [low, high]
+  | 'in_last_days' | 'older_than_days'; // value: number of days
+
+export interface SortKey {
+  column: string;
+  asc?: boolean; // default true
+  nulls?: 'first' | 'last' | null; // default: database default
+}
+
+// WHERE condition tree; a flat list of conditions is ANDed as before.
+export type FilterNode =
+  | FilterCond
+  | { all: FilterNode[] }
+  | { any: FilterNode[] }
+  | { not: FilterNode };
+
+// One per constraint: `columns[i]` references `ref_columns[i]` of `to_table`.
+export interface ForeignKeyDef {
+  name?: string | null; // SQLite reports none
+  columns: string[];
+  ref_schema?: string | null;
+  to_table: string;
+  ref_columns: string[];
+  on_update?: string | null; // e.g. 'CASCADE', 'NO ACTION'
+  on_delete?: string | null;
+}
+
+// Join along a foreign key from `from` (default: the base table) to
+// `foreign_key.to_table`, visible as `alias` (default: the table name).
+export interface JoinSpec {
+  kind?: 'inner' | 'left'; // default 'left'
+  foreign_key: ForeignKeyDef;
+  from?: string | null;
+  alias?: string | null;
+}
+
+export interface AggregateSpec {
+  func: 'count' | 'count_distinct' | 'sum' | 'avg' | 'min' | 'max';
+  column: string | null; // null with 'count' counts rows
+  alias?: string | null; // default e.g. 'sum_amount', or 'count' for rows
+}
+
+// With joins, columns/filters/sort may use `alias.column`; joined columns
+// come back labelled that way.
 export interface SelectSpec {
+  schema?: string | null; // Postgres schema / MySQL database; default: the connection's
   table: string;
   columns: string[];
-  filters: FilterCond[];
-  sort?: [string, boolean] | null; // [column, asc]
+  filters: FilterNode[]; // combined with AND
+  sort?: [string, boolean] | SortKey[] | null; // [column, asc] or several keys
   limit?: number | null;
   offset?: number | null;
+  joins?: JoinSpec[];
+  aggregates?: AggregateSpec[]; // selected after `columns`
+  group_by?: string[]; // also selected when `columns` is empty
+  having?: FilterNode[]; // a column may name an aggregate alias
+  keyset?: Keyset | null; // page by cursor instead of offset; needs `limit`
+}
+
+// Sort columns must be NOT NULL; the primary key (or `unique_key`) breaks ties.
+export interface Keyset {
+  after?: string | null;
+  before?: string | null;
+  unique_key?: string[];
+}
+
+// Row edits from the grid. `values` are [column, value] pairs.
+export interface InsertSpec {
+  schema?: string | null;
+  table: string;
+  values: Array<[string, unknown]>;
+}
+
+// Rows hold values in `columns` order; large inputs are split into several
+// statements in one transaction.
+export interface BulkInsertSpec {
+  schema?: string | null;
+  table: string;
+  columns: string[];
+  rows: unknown[][];
+  upsert?: Upsert | null;
+}
+
+// `update` defaults to every inserted column outside `conflict`; with none
+// the colliding rows are left unchanged.
+export interface Upsert {
+  conflict: string[];
+  update?: string[];
+}
+
+export interface UpdateSpec {
+  schema?: string | null;
+  table: string;
+  values: Array<[string, unknown]>;
+  filters: FilterCond[];
+}
+
+export interface DeleteSpec {
+  schema?: string | null;
+  table: string;
+  filters: FilterCond[];
+}
+
+// Grid edits for one table, applied together. `key` holds the primary key
+// values of the row as loaded.
+export type RowChange =
+  | { kind: 'insert'; values: Array<[string, unknown]> }
+  | {
+      kind: 'update';
+      key: Array<[string, unknown]>;
+      values: Array<[string, unknown]>;
+    }
+  | { kind: 'delete'; key: Array<[string, unknown]> };
+
+export interface ChangeSet {
+  schema?: string | null;
+  table: string;
+  changes: RowChange[];
+}
+
+export interface ChangeSetResult {
+  sql: string[]; // one statement per change, values inlined
+  applied: boolean;
+  rows_affected: number;
+  // `index` points into `changes`; `column` is set for cell-level problems
+  error: { index: number; column: string | null; message: string } | null;
 }
 
 // Database schema as returned by the backend. Keep it flexible to avoid tight coupling.
@@ -46,15 +246,45 @@ export interface DatabaseSchema {
       precision?: number | null;
       scale?: number | null;
     }>;
-    foreign_keys: Array<{
-      from: string;
-      ref_schema?: string | null;
-      to_table: string;
-      to: string;
-    }>;
+    foreign_keys: ForeignKeyDef[];
+    indexes: IndexDef[];
+    unique_constraints: Array<{ name: string; columns: string[] }>;
+    checks: Array<{ name: string | null; expression: string }>; // SQLite checks may be unnamed
   }>;
 }
 
+export type TableDef = DatabaseSchema['tables'][number];
+
+// Narrows schema loading. Patterns use SQL LIKE syntax (`%`, `_`) and ignore case.
+export interface ObjectFilter {
+  schemas?: string[]; // exact names, e.g. ['public', 'analytics']
+  schema_pattern?: string | null;
+  name_pattern?: string | null;
+}
+
+// Names only, from one catalog query; load details with describeTable(s).
+export interface ObjectList {
+  dialect: string;
+  schemas: string[];
+  objects: Array<{ schema: string; name: string; kind: 'table' | 'view' }>;
+}
+
+// A table by name; without `schema` it matches in any schema.
+export interface ObjectRef {
+  schema?: string | null;
+  name: string;
+}
+
+// Includes the indexes behind primary keys and unique constraints.
+export interface IndexDef {
+  name: string;
+  columns: string[]; // key order; expression keys as SQL where known
+  unique: boolean;
+  primary: boolean;
+  predicate: string | null; // WHERE clause of a partial index
+  method: string | null; // e.g. 'btree', 'gin', 'FULLTEXT'
+}
+
 // --- Internal helper to normalise errors into Error instances ---
 function toError(err: unknown): Error {
   if (err instanceof Error) return err;
@@ -85,14 +315,24 @@ export async function closeConnection(connId: string): Promise<void> {
   }
 }
 
+// Pass a `queryId` to be able to abort the query with `cancelQuery`, and a
+// `sessionId` from `beginTransaction` to run inside that transaction.
 export async function executeSql(
   connId: string,
   sql: string,
-  limit = 1000
+  limit = 1000,
+  queryId?: string,
+  sessionId?: string
 ): Promise<QueryResult> {
   try {
     return await invoke<QueryResult>('execute_sql', {
-      args: { conn_id: connId, sql, limit },
+      args: {
+        conn_id: connId,
+        sql,
+        limit,
+        query_id: queryId,
+        session_id: sessionId,
+      },
     });
   } catch (e) {
     throw toError(e);
@@ -101,10 +341,143 @@ export async function executeSql(
 
 export async function executeSelectSpec(
   connId: string,
-  spec: SelectSpec
+  spec: SelectSpec,
+  queryId?: string,
+  sessionId?: string,
+  count?: CountMode
 ): Promise<QueryResult> {
   try {
     return await invoke<QueryResult>('execute_select_spec', {
+      args: { conn_id: connId, spec, query_id: queryId, session_id: sessionId, count },
+    });
+  } catch (e) {
+    throw toError(e);
+  }
+}
+
+// Runs a `;`-separated script statement by statement on one connection.
+export async function executeScript(
+  connId: string,
+  sql: string,
+  options: ScriptOptions = {},
+  limit = 1000,
+  queryId?: string
+): Promise<ScriptResult> {
+  try {
+    return await invoke<ScriptResult>('execute_script', {
+      args: { conn_id: connId, sql, limit, ...options, query_id: queryId },
+    });
+  } catch (e) {
+    throw toError(e);
+  }
+}
+
+// Returns `rows_affected` and, where the driver reports it, `last_insert_id`.
+export async function executeInsertSpec(
+  connId: string,
+  spec: InsertSpec,
+  sessionId?: string
+): Promise<QueryResult> {
+  try {
+    return await invoke<QueryResult>('execute_insert_spec', {
+      args: { conn_id: connId, spec, session_id: sessionId },
+    });
+  } catch (e) {
+    throw toError(e);
+  }
+}
+
+export async function executeBulkInsert(
+  connId: string,
+  spec: BulkInsertSpec,
+  sessionId?: string
+): Promise<QueryResult> {
+  try {
+    return await invoke<QueryResult>('execute_bulk_insert', {
+      args: { conn_id: connId, spec, session_id: sessionId },
+    });
+  } catch (e) {
+    throw toError(e);
+  }
+}
+
+// Rejected unless the filters pin one row by primary key or `allowUnpinned` is set.
+export async function executeUpdateSpec(
+  connId: string,
+  spec: UpdateSpec,
+  sessionId?: string,
+  allowUnpinned = false
+): Promise<QueryResult> {
+  try {
+    return await invoke<QueryResult>('execute_update_spec', {
+      args: {
+        conn_id: connId,
+        spec,
+        session_id: sessionId,
+        allow_unpinned: allowUnpinned,
+      },
+    });
+  } catch (e) {
+    throw toError(e);
+  }
+}
+
+export async function executeDeleteSpec(
+  connId: string,
+  spec: DeleteSpec,
+  sessionId?: string,
+  allowUnpinned = false
+): Promise<QueryResult> {
+  try {
+    return await invoke<QueryResult>('execute_delete_spec', {
+      args: {
+        conn_id: connId,
+        spec,
+        session_id: sessionId,
+        allow_unpinned: allowUnpinned,
+      },
+    });
+  } catch (e) {
+    throw toError(e);
+  }
+}
+
+// Any builder spec, tagged for `validateSpec` / `previewSpecSql`.
+export type Spec =
+  | ({ kind: 'select' } & SelectSpec)
+  | ({ kind: 'insert' } & InsertSpec)
+  | ({ kind: 'bulk_insert' } & BulkInsertSpec)
+  | ({ kind: 'update' } & UpdateSpec)
+  | ({ kind: 'delete' } & DeleteSpec);
+
+// `field` is a path into the spec, e.g. 'filters[0].any[1].value'.
+export interface SpecError {
+  field: string;
+  message: string;
+}
+
+// Check a spec against the connection's schema; empty when it is valid.
+// The execute*Spec commands run the same checks and reject invalid specs.
+export async function validateSpec(connId: string, spec: Spec): Promise<SpecError[]> {
+  try {
+    return await invoke<SpecError[]>('validate_spec', {
+      args: { conn_id: connId, spec },
+    });
+  } catch (e) {
+    throw toError(e);
+  }
+}
+
+export interface SpecSql {
+  sql: string; // values inlined as quoted literals, for display
+  parameterized: string; // as sent to the database
+  binds: unknown[]; // values for the placeholders, in order
+}
+
+// SQL the backend would run for a spec, without running it.
+export async function previewSpecSql(connId: string, spec: Spec): Promise<SpecSql> {
+  try {
+    return await invoke<SpecSql>('preview_spec_sql', {
       args: { conn_id: connId, spec },
     });
   } catch (e) {
@@ -112,9 +485,161 @@ export async function executeSelectSpec(
   }
 }
 
-export async function getSchema(connId: string): Promise<DatabaseSchema> {
+export async function previewChangeSet(
+  connId: string,
+  changeSet: ChangeSet
+): Promise<ChangeSetResult> {
+  try {
+    return await invoke<ChangeSetResult>('preview_change_set', {
+      args: { conn_id: connId, change_set: changeSet },
+    });
+  } catch (e) {
+    throw toError(e);
+  }
+}
+
+// All or nothing: on failure nothing is applied and `error` says where.
+export async function applyChangeSet(
+  connId: string,
+  changeSet: ChangeSet
+): Promise<ChangeSetResult> {
+  try {
+    return await invoke<ChangeSetResult>('apply_change_set', {
+      args: { conn_id: connId, change_set: changeSet },
+    });
+  } catch (e) {
+    throw toError(e);
+  }
+}
+
+// Streams rows to `onEvent` while the query runs; the resolved result has empty `rows`.
+export async function executeSqlStream(
+  connId: string,
+  sql: string,
+  onEvent: (ev: QueryEvent) => void,
+  limit = 1000,
+  batchSize = 200,
+  sessionId?: string
+): Promise<QueryResult> {
+  const channel = new Channel<QueryEvent>();
+  channel.onmessage = onEvent;
+  try {
+    return await invoke<QueryResult>('execute_sql_stream', {
+      args: {
+        conn_id: connId,
+        sql,
+        limit,
+        batch_size: batchSize,
+        session_id: sessionId,
+      },
+      onEvent: channel,
+    });
+  } catch (e) {
+    throw toError(e);
+  }
+}
+
+export async function executeSelectSpecStream(
+  connId: string,
+  spec: SelectSpec,
+  onEvent: (ev: QueryEvent) => void,
+  batchSize = 200,
+  sessionId?: string
+): Promise<QueryResult> {
+  const channel = new Channel<QueryEvent>();
+  channel.onmessage = onEvent;
+  try {
+    return await invoke<QueryResult>('execute_select_spec_stream', {
+      args: {
+        conn_id: connId,
+        spec,
+        batch_size: batchSize,
+        session_id: sessionId,
+      },
+      onEvent: channel,
+    });
+  } catch (e) {
+    throw toError(e);
+  }
+}
+
+// Resolves to false if the query had already finished.
+export async function cancelQuery(queryId: string): Promise<boolean> {
+  try {
+    return await invoke<boolean>('cancel_query', {
+      args: { query_id: queryId },
+    });
+  } catch (e) {
+    throw toError(e);
+  }
+}
+
+// Closing the connection rolls back any transaction still open on it.
+export async function beginTransaction(
+  connId: string
+): Promise<TransactionState> {
+  try {
+    return await invoke<TransactionState>('begin_transaction', {
+      args: { conn_id: connId },
+    });
+  } catch (e) {
+    throw toError(e);
+  }
+}
+
+export async function commit(sessionId: string): Promise<void> {
+  try {
+    await invoke('commit', { args: { session_id: sessionId } });
+  } catch (e) {
+    throw toError(e);
+  }
+}
+
+export async function rollback(sessionId: string): Promise<void> {
+  try {
+    await invoke('rollback', { args: { session_id: sessionId } });
+  } catch (e) {
+    throw toError(e);
+  }
+}
+
+export async function transactionState(
+  sessionId: string
+): Promise<TransactionState> {
+  try {
+    return await invoke<TransactionState>('transaction_state', {
+      args: { session_id: sessionId },
+    });
+  } catch (e) {
+    throw toError(e);
+  }
+}
+
+// Served from the backend cache unless `refresh` is set. With a filter only
+// the matching tables are introspected (and not cached).
+export async function getSchema(
+  connId: string,
+  filter?: ObjectFilter,
+  refresh = false
+): Promise<DatabaseSchema> {
   try {
     return await invoke<DatabaseSchema>('get_schema', {
+      args: {
+        conn_id: connId,
+        ...(filter ? { filter } : {}),
+        ...(refresh ? { refresh } : {}),
+      },
+    });
+  } catch (e) {
+    throw toError(e);
+  }
+}
+
+// Cheap check for DDL since the schema was loaded, e.g. a migration run by
+// another client; true means "schema changed, refresh?".
+export async function schemaChanged(connId: string): Promise<boolean> {
+  try {
+    return await invoke<boolean>('schema_changed', {
       args: { conn_id: connId },
     });
   } catch (e) {
@@ -122,6 +647,47 @@ export async function getSchema(connId: string): Promise<DatabaseSchema> {
   }
 }
 
+export async function listObjects(
+  connId: string,
+  filter: ObjectFilter = {}
+): Promise<ObjectList> {
+  try {
+    return await invoke<ObjectList>('list_objects', {
+      args: { conn_id: connId, ...filter },
+    });
+  } catch (e) {
+    throw toError(e);
+  }
+}
+
+export async function describeTable(
+  connId: string,
+  table: string,
+  schema?: string | null
+): Promise<TableDef> {
+  try {
+    return await invoke<TableDef>('describe_table', {
+      args: { conn_id: connId, schema: schema ?? null, table },
+    });
+  } catch (e) {
+    throw toError(e);
+  }
+}
+
+// Details for several tables at once, e.g. those visible in the ER view.
+export async function describeTables(
+  connId: string,
+  tables: ObjectRef[]
+): Promise<TableDef[]> {
+  try {
+    return await invoke<TableDef[]>('describe_tables', {
+      args: { conn_id: connId, tables },
+    });
+  } catch (e) {
+    throw toError(e);
+  }
+}
+
 // --- Optional convenience wrappers used by the UI ---
 // NOTE: On macOS, the backend should open the dialog with security-scoped access enabled
 // (e.g. NSOpenPanel with security-scoped bookmarks) so the selected path can be accessed.
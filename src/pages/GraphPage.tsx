@@ -228,8 +228,8 @@ export default function GraphPage({ onExit }: { onExit: () => void }) {
         }));
         const fks: ForeignKey[] = raw.tables.flatMap((t) =>
           (t.foreign_keys ?? []).map((fk) => ({
-            from: { table: t.name, column: fk.from },
-            to: { table: fk.to_table, column: fk.to },
+            from: { table: t.name, column: fk.columns.join(', ') },
+            to: { table: fk.to_table, column: fk.ref_columns.join(', ') },
           }))
         );
         setActiveSchema({ tables, fks });